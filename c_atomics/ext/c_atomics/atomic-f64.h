@@ -0,0 +1,51 @@
+#include "rust-atomics.h"
+#include <ruby.h>
+
+const rb_data_type_t atomic_f64_data = {
+    .function = {.dfree = RUBY_DEFAULT_FREE},
+    .flags = RUBY_TYPED_FROZEN_SHAREABLE};
+
+VALUE rb_atomic_f64_alloc(VALUE klass) {
+  atomic_f64_t *atomic;
+  TypedData_Make_Struct0(obj, klass, atomic_f64_t, ATOMIC_F64_SIZE,
+                         &atomic_f64_data, atomic);
+  atomic_f64_init(atomic, 0.0);
+  VALUE rb_cRactor = rb_const_get(rb_cObject, rb_intern("Ractor"));
+  rb_funcall(rb_cRactor, rb_intern("make_shareable"), 1, obj);
+  return obj;
+}
+
+VALUE rb_atomic_f64_add(VALUE self, VALUE delta) {
+  atomic_f64_t *atomic;
+  TypedData_Get_Struct(self, atomic_f64_t, &atomic_f64_data, atomic);
+  return DBL2NUM(atomic_f64_add(atomic, NUM2DBL(delta)));
+}
+
+VALUE rb_atomic_f64_read(VALUE self) {
+  atomic_f64_t *atomic;
+  TypedData_Get_Struct(self, atomic_f64_t, &atomic_f64_data, atomic);
+  return DBL2NUM(atomic_f64_read(atomic));
+}
+
+VALUE rb_atomic_f64_swap(VALUE self, VALUE n) {
+  atomic_f64_t *atomic;
+  TypedData_Get_Struct(self, atomic_f64_t, &atomic_f64_data, atomic);
+  return DBL2NUM(atomic_f64_swap(atomic, NUM2DBL(n)));
+}
+
+VALUE rb_atomic_f64_get_and_reset(VALUE self) {
+  atomic_f64_t *atomic;
+  TypedData_Get_Struct(self, atomic_f64_t, &atomic_f64_data, atomic);
+  return DBL2NUM(atomic_f64_get_and_reset(atomic));
+}
+
+static void init_atomic_f64(VALUE rb_mCAtomics) {
+  VALUE rb_cAtomicF64 =
+      rb_define_class_under(rb_mCAtomics, "AtomicF64", rb_cObject);
+  rb_define_alloc_func(rb_cAtomicF64, rb_atomic_f64_alloc);
+  rb_define_method(rb_cAtomicF64, "add", rb_atomic_f64_add, 1);
+  rb_define_method(rb_cAtomicF64, "read", rb_atomic_f64_read, 0);
+  rb_define_method(rb_cAtomicF64, "swap", rb_atomic_f64_swap, 1);
+  rb_define_method(rb_cAtomicF64, "get_and_reset", rb_atomic_f64_get_and_reset,
+                   0);
+}
@@ -0,0 +1,78 @@
+#include "rust-atomics.h"
+#include <ruby.h>
+
+const rb_data_type_t atomic_i64_counter_data = {
+    .function = {.dfree = RUBY_DEFAULT_FREE},
+    .flags = RUBY_TYPED_FROZEN_SHAREABLE};
+
+VALUE rb_atomic_i64_counter_alloc(VALUE klass) {
+  atomic_i64_counter_t *counter;
+  TypedData_Make_Struct0(obj, klass, atomic_i64_counter_t,
+                         ATOMIC_I64_COUNTER_SIZE, &atomic_i64_counter_data,
+                         counter);
+  atomic_i64_counter_init(counter, 0);
+  VALUE rb_cRactor = rb_const_get(rb_cObject, rb_intern("Ractor"));
+  rb_funcall(rb_cRactor, rb_intern("make_shareable"), 1, obj);
+  return obj;
+}
+
+VALUE rb_atomic_i64_counter_increment(VALUE self) {
+  atomic_i64_counter_t *counter;
+  TypedData_Get_Struct(self, atomic_i64_counter_t, &atomic_i64_counter_data,
+                       counter);
+  atomic_i64_counter_increment(counter);
+  return Qnil;
+}
+
+VALUE rb_atomic_i64_counter_decrement(VALUE self) {
+  atomic_i64_counter_t *counter;
+  TypedData_Get_Struct(self, atomic_i64_counter_t, &atomic_i64_counter_data,
+                       counter);
+  atomic_i64_counter_decrement(counter);
+  return Qnil;
+}
+
+VALUE rb_atomic_i64_counter_add(VALUE self, VALUE delta) {
+  atomic_i64_counter_t *counter;
+  TypedData_Get_Struct(self, atomic_i64_counter_t, &atomic_i64_counter_data,
+                       counter);
+  return LL2NUM(atomic_i64_counter_add(counter, NUM2LL(delta)));
+}
+
+VALUE rb_atomic_i64_counter_read(VALUE self) {
+  atomic_i64_counter_t *counter;
+  TypedData_Get_Struct(self, atomic_i64_counter_t, &atomic_i64_counter_data,
+                       counter);
+  return LL2NUM(atomic_i64_counter_read(counter));
+}
+
+VALUE rb_atomic_i64_counter_swap(VALUE self, VALUE n) {
+  atomic_i64_counter_t *counter;
+  TypedData_Get_Struct(self, atomic_i64_counter_t, &atomic_i64_counter_data,
+                       counter);
+  return LL2NUM(atomic_i64_counter_swap(counter, NUM2LL(n)));
+}
+
+VALUE rb_atomic_i64_counter_get_and_reset(VALUE self) {
+  atomic_i64_counter_t *counter;
+  TypedData_Get_Struct(self, atomic_i64_counter_t, &atomic_i64_counter_data,
+                       counter);
+  return LL2NUM(atomic_i64_counter_get_and_reset(counter));
+}
+
+static void init_atomic_i64_counter(VALUE rb_mCAtomics) {
+  VALUE rb_cAtomicI64Counter =
+      rb_define_class_under(rb_mCAtomics, "AtomicI64Counter", rb_cObject);
+  rb_define_alloc_func(rb_cAtomicI64Counter, rb_atomic_i64_counter_alloc);
+  rb_define_method(rb_cAtomicI64Counter, "increment",
+                   rb_atomic_i64_counter_increment, 0);
+  rb_define_method(rb_cAtomicI64Counter, "decrement",
+                   rb_atomic_i64_counter_decrement, 0);
+  rb_define_method(rb_cAtomicI64Counter, "add", rb_atomic_i64_counter_add, 1);
+  rb_define_method(rb_cAtomicI64Counter, "read", rb_atomic_i64_counter_read,
+                   0);
+  rb_define_method(rb_cAtomicI64Counter, "swap", rb_atomic_i64_counter_swap,
+                   1);
+  rb_define_method(rb_cAtomicI64Counter, "get_and_reset",
+                   rb_atomic_i64_counter_get_and_reset, 0);
+}
@@ -0,0 +1,68 @@
+#include "rust-atomics.h"
+#include <ruby.h>
+
+void rb_atomic_ref_mark(void *);
+
+const rb_data_type_t atomic_ref_data = {
+    .function = {.dfree = RUBY_DEFAULT_FREE, .dmark = rb_atomic_ref_mark},
+    .flags = RUBY_TYPED_FROZEN_SHAREABLE};
+
+void rb_atomic_ref_mark(void *ptr) {
+  atomic_ref_t *atomic_ref = ptr;
+  atomic_ref_mark(atomic_ref, rb_gc_mark);
+}
+
+VALUE rb_atomic_ref_alloc(VALUE klass) {
+  atomic_ref_t *atomic_ref;
+  TypedData_Make_Struct0(obj, klass, atomic_ref_t, ATOMIC_REF_SIZE,
+                         &atomic_ref_data, atomic_ref);
+  atomic_ref_init(atomic_ref, Qnil);
+  VALUE rb_cRactor = rb_const_get(rb_cObject, rb_intern("Ractor"));
+  rb_funcall(rb_cRactor, rb_intern("make_shareable"), 1, obj);
+  return obj;
+}
+
+VALUE rb_atomic_ref_initialize(VALUE self, VALUE value) {
+  atomic_ref_t *atomic_ref;
+  TypedData_Get_Struct(self, atomic_ref_t, &atomic_ref_data, atomic_ref);
+  atomic_ref_set(atomic_ref, value);
+  return Qnil;
+}
+
+VALUE rb_atomic_ref_get(VALUE self) {
+  atomic_ref_t *atomic_ref;
+  TypedData_Get_Struct(self, atomic_ref_t, &atomic_ref_data, atomic_ref);
+  return atomic_ref_get(atomic_ref);
+}
+
+VALUE rb_atomic_ref_set(VALUE self, VALUE value) {
+  atomic_ref_t *atomic_ref;
+  TypedData_Get_Struct(self, atomic_ref_t, &atomic_ref_data, atomic_ref);
+  atomic_ref_set(atomic_ref, value);
+  return Qnil;
+}
+
+VALUE rb_atomic_ref_swap(VALUE self, VALUE value) {
+  atomic_ref_t *atomic_ref;
+  TypedData_Get_Struct(self, atomic_ref_t, &atomic_ref_data, atomic_ref);
+  return atomic_ref_swap(atomic_ref, value);
+}
+
+VALUE rb_atomic_ref_compare_and_set(VALUE self, VALUE expected, VALUE value) {
+  atomic_ref_t *atomic_ref;
+  TypedData_Get_Struct(self, atomic_ref_t, &atomic_ref_data, atomic_ref);
+  return atomic_ref_compare_and_set(atomic_ref, expected, value) ? Qtrue
+                                                                  : Qfalse;
+}
+
+static void init_atomic_ref(VALUE rb_mCAtomics) {
+  VALUE rb_cAtomicRef =
+      rb_define_class_under(rb_mCAtomics, "AtomicRef", rb_cObject);
+  rb_define_alloc_func(rb_cAtomicRef, rb_atomic_ref_alloc);
+  rb_define_method(rb_cAtomicRef, "initialize", rb_atomic_ref_initialize, 1);
+  rb_define_method(rb_cAtomicRef, "get", rb_atomic_ref_get, 0);
+  rb_define_method(rb_cAtomicRef, "set", rb_atomic_ref_set, 1);
+  rb_define_method(rb_cAtomicRef, "swap", rb_atomic_ref_swap, 1);
+  rb_define_method(rb_cAtomicRef, "compare_and_set",
+                   rb_atomic_ref_compare_and_set, 2);
+}
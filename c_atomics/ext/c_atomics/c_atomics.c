@@ -1,12 +1,23 @@
+#include "atomic-f64.h"
+#include "atomic-i64-counter.h"
+#include "atomic-ref.h"
+#include "counter-map.h"
 #include "counter.h"
 #include "fixed-size-object-pool.h"
 #include "hashmap.h"
+#include "histogram.h"
 #include "log-on-mark.h"
+#include "lru-cache.h"
+#include "metrics-registry.h"
 #include "mpmc-queue.h"
 #include "object-address.h"
 #include "plain-counter.h"
 #include "queue-with-mutex.h"
+#include "set.h"
+#include "skip-list-map.h"
 #include "slow-object.h"
+#include "striped-counter.h"
+#include "ttl-map.h"
 #include <ruby.h>
 
 RUBY_FUNC_EXPORTED void Init_c_atomics(void) {
@@ -16,7 +27,18 @@ RUBY_FUNC_EXPORTED void Init_c_atomics(void) {
 
   init_plain_counter(rb_mCAtomics);
   init_counter(rb_mCAtomics);
+  init_striped_counter(rb_mCAtomics);
+  init_atomic_i64_counter(rb_mCAtomics);
+  init_atomic_f64(rb_mCAtomics);
+  init_atomic_ref(rb_mCAtomics);
+  init_histogram(rb_mCAtomics);
+  init_metrics_registry(rb_mCAtomics);
   init_hashmap(rb_mCAtomics);
+  init_lru_cache(rb_mCAtomics);
+  init_ttl_map(rb_mCAtomics);
+  init_set(rb_mCAtomics);
+  init_counter_map(rb_mCAtomics);
+  init_skip_list_map(rb_mCAtomics);
   init_fixed_size_object_pool(rb_mCAtomics);
   init_queue_with_mutex(rb_mCAtomics);
   init_slow_object(rb_mCAtomics);
@@ -0,0 +1,109 @@
+#include "rust-atomics.h"
+#include <ruby.h>
+
+void rb_concurrent_counter_map_mark(void *);
+void rb_concurrent_counter_map_free(void *);
+
+const rb_data_type_t concurrent_counter_map_data = {
+    .function = {.dfree = rb_concurrent_counter_map_free,
+                 .dmark = rb_concurrent_counter_map_mark},
+    .flags = RUBY_TYPED_FROZEN_SHAREABLE};
+
+void rb_concurrent_counter_map_free(void *ptr) {
+  concurrent_counter_map_t *counter_map = ptr;
+  concurrent_counter_map_drop(counter_map);
+}
+
+void rb_concurrent_counter_map_mark(void *ptr) {
+  concurrent_counter_map_t *counter_map = ptr;
+  concurrent_counter_map_mark(counter_map, rb_gc_mark);
+}
+
+VALUE rb_concurrent_counter_map_alloc(VALUE klass) {
+  concurrent_counter_map_t *counter_map;
+  TypedData_Make_Struct0(obj, klass, concurrent_counter_map_t,
+                         CONCURRENT_COUNTER_MAP_SIZE,
+                         &concurrent_counter_map_data, counter_map);
+  concurrent_counter_map_init(counter_map);
+  VALUE rb_cRactor = rb_const_get(rb_cObject, rb_intern("Ractor"));
+  rb_funcall(rb_cRactor, rb_intern("make_shareable"), 1, obj);
+  return obj;
+}
+
+VALUE rb_concurrent_counter_map_increment(int argc, VALUE *argv, VALUE self) {
+  VALUE key, delta;
+  rb_scan_args(argc, argv, "11", &key, &delta);
+  concurrent_counter_map_t *counter_map;
+  TypedData_Get_Struct(self, concurrent_counter_map_t,
+                       &concurrent_counter_map_data, counter_map);
+  uint64_t value = concurrent_counter_map_increment(
+      counter_map, key, NIL_P(delta) ? 1 : NUM2ULL(delta));
+  return ULL2NUM(value);
+}
+
+VALUE rb_concurrent_counter_map_get(VALUE self, VALUE key) {
+  concurrent_counter_map_t *counter_map;
+  TypedData_Get_Struct(self, concurrent_counter_map_t,
+                       &concurrent_counter_map_data, counter_map);
+  return ULL2NUM(concurrent_counter_map_get(counter_map, key));
+}
+
+VALUE rb_concurrent_counter_map_top_n(VALUE self, VALUE n) {
+  long requested = NUM2LONG(n);
+  if (requested < 0) {
+    rb_raise(rb_eArgError, "negative n");
+  }
+  concurrent_counter_map_t *counter_map;
+  TypedData_Get_Struct(self, concurrent_counter_map_t,
+                       &concurrent_counter_map_data, counter_map);
+  // never more than stored keys, so the buffer size can't overflow;
+  // keys added concurrently are cut off by top_n writing at most `capa`
+  size_t capa = concurrent_counter_map_size(counter_map);
+  if ((size_t)requested < capa) {
+    capa = requested;
+  }
+  // keys go into a GC-visible buffer, they may be deleted concurrently
+  VALUE keys_tmp, counts_tmp;
+  VALUE *keys = rb_alloc_tmp_buffer(&keys_tmp, sizeof(VALUE) * capa);
+  uint64_t *counts = rb_alloc_tmp_buffer(&counts_tmp, sizeof(uint64_t) * capa);
+  size_t len = concurrent_counter_map_top_n(counter_map, capa, keys, counts);
+  VALUE result = rb_ary_new_capa(len);
+  for (size_t i = 0; i < len; i++) {
+    rb_ary_push(result, rb_assoc_new(keys[i], ULL2NUM(counts[i])));
+  }
+  rb_free_tmp_buffer(&counts_tmp);
+  rb_free_tmp_buffer(&keys_tmp);
+  return result;
+}
+
+VALUE rb_concurrent_counter_map_size(VALUE self) {
+  concurrent_counter_map_t *counter_map;
+  TypedData_Get_Struct(self, concurrent_counter_map_t,
+                       &concurrent_counter_map_data, counter_map);
+  return SIZET2NUM(concurrent_counter_map_size(counter_map));
+}
+
+VALUE rb_concurrent_counter_map_reset(VALUE self) {
+  concurrent_counter_map_t *counter_map;
+  TypedData_Get_Struct(self, concurrent_counter_map_t,
+                       &concurrent_counter_map_data, counter_map);
+  concurrent_counter_map_reset(counter_map);
+  return Qnil;
+}
+
+static void init_counter_map(VALUE rb_mCAtomics) {
+  VALUE rb_cConcurrentCounterMap = rb_define_class_under(
+      rb_mCAtomics, "ConcurrentCounterMap", rb_cObject);
+  rb_define_alloc_func(rb_cConcurrentCounterMap,
+                       rb_concurrent_counter_map_alloc);
+  rb_define_method(rb_cConcurrentCounterMap, "increment",
+                   rb_concurrent_counter_map_increment, -1);
+  rb_define_method(rb_cConcurrentCounterMap, "get",
+                   rb_concurrent_counter_map_get, 1);
+  rb_define_method(rb_cConcurrentCounterMap, "top_n",
+                   rb_concurrent_counter_map_top_n, 1);
+  rb_define_method(rb_cConcurrentCounterMap, "size",
+                   rb_concurrent_counter_map_size, 0);
+  rb_define_method(rb_cConcurrentCounterMap, "reset",
+                   rb_concurrent_counter_map_reset, 0);
+}
@@ -9,30 +9,122 @@ VALUE rb_atomic_counter_alloc(VALUE klass) {
   atomic_counter_t *counter;
   TypedData_Make_Struct0(obj, klass, atomic_counter_t, ATOMIC_COUNTER_SIZE,
                          &atomic_counter_data, counter);
-  atomic_counter_init(counter, 0);
+  atomic_counter_init(counter, 0, ATOMIC_COUNTER_OVERFLOW_WRAPPING);
   VALUE rb_cRactor = rb_const_get(rb_cObject, rb_intern("Ractor"));
   rb_funcall(rb_cRactor, rb_intern("make_shareable"), 1, obj);
   return obj;
 }
 
+static uint8_t rb_atomic_counter_overflow_mode(VALUE overflow) {
+  if (NIL_P(overflow) || overflow == ID2SYM(rb_intern("wrapping"))) {
+    return ATOMIC_COUNTER_OVERFLOW_WRAPPING;
+  } else if (overflow == ID2SYM(rb_intern("saturating"))) {
+    return ATOMIC_COUNTER_OVERFLOW_SATURATING;
+  } else if (overflow == ID2SYM(rb_intern("checked"))) {
+    return ATOMIC_COUNTER_OVERFLOW_CHECKED;
+  }
+  rb_raise(rb_eArgError,
+           "overflow must be one of :wrapping, :saturating or :checked");
+}
+
+VALUE rb_atomic_counter_initialize(int argc, VALUE *argv, VALUE self) {
+  VALUE overflow;
+  rb_scan_args(argc, argv, "01", &overflow);
+  atomic_counter_t *counter;
+  TypedData_Get_Struct(self, atomic_counter_t, &atomic_counter_data, counter);
+  if (!atomic_counter_init(counter, 0,
+                           rb_atomic_counter_overflow_mode(overflow))) {
+    rb_raise(rb_eArgError, "unknown overflow mode");
+  }
+  return Qnil;
+}
+
 VALUE rb_atomic_counter_increment(VALUE self) {
   atomic_counter_t *counter;
   TypedData_Get_Struct(self, atomic_counter_t, &atomic_counter_data, counter);
-  atomic_counter_increment(counter);
+  if (!atomic_counter_increment(counter)) {
+    rb_raise(rb_eRangeError, "counter overflow");
+  }
   return Qnil;
 }
 
 VALUE rb_atomic_counter_read(VALUE self) {
   atomic_counter_t *counter;
   TypedData_Get_Struct(self, atomic_counter_t, &atomic_counter_data, counter);
-  return LONG2FIX(atomic_counter_read(counter));
+  return ULL2NUM(atomic_counter_read(counter));
+}
+
+VALUE rb_atomic_counter_add(VALUE self, VALUE delta) {
+  atomic_counter_t *counter;
+  TypedData_Get_Struct(self, atomic_counter_t, &atomic_counter_data, counter);
+  uint64_t previous;
+  if (!atomic_counter_add(counter, NUM2ULL(delta), &previous)) {
+    rb_raise(rb_eRangeError, "counter overflow");
+  }
+  return ULL2NUM(previous);
+}
+
+VALUE rb_atomic_counter_sub(VALUE self, VALUE delta) {
+  atomic_counter_t *counter;
+  TypedData_Get_Struct(self, atomic_counter_t, &atomic_counter_data, counter);
+  uint64_t previous;
+  if (!atomic_counter_sub(counter, NUM2ULL(delta), &previous)) {
+    rb_raise(rb_eRangeError, "counter underflow");
+  }
+  return ULL2NUM(previous);
+}
+
+VALUE rb_atomic_counter_swap(VALUE self, VALUE n) {
+  atomic_counter_t *counter;
+  TypedData_Get_Struct(self, atomic_counter_t, &atomic_counter_data, counter);
+  return ULL2NUM(atomic_counter_swap(counter, NUM2ULL(n)));
+}
+
+VALUE rb_atomic_counter_compare_and_set(VALUE self, VALUE expected,
+                                        VALUE desired) {
+  atomic_counter_t *counter;
+  TypedData_Get_Struct(self, atomic_counter_t, &atomic_counter_data, counter);
+  bool swapped = atomic_counter_compare_exchange(counter, NUM2ULL(expected),
+                                                 NUM2ULL(desired), NULL);
+  return swapped ? Qtrue : Qfalse;
+}
+
+VALUE rb_atomic_counter_fetch_max(VALUE self, VALUE n) {
+  atomic_counter_t *counter;
+  TypedData_Get_Struct(self, atomic_counter_t, &atomic_counter_data, counter);
+  return ULL2NUM(atomic_counter_fetch_max(counter, NUM2ULL(n)));
+}
+
+VALUE rb_atomic_counter_fetch_min(VALUE self, VALUE n) {
+  atomic_counter_t *counter;
+  TypedData_Get_Struct(self, atomic_counter_t, &atomic_counter_data, counter);
+  return ULL2NUM(atomic_counter_fetch_min(counter, NUM2ULL(n)));
+}
+
+VALUE rb_atomic_counter_get_and_reset(VALUE self) {
+  atomic_counter_t *counter;
+  TypedData_Get_Struct(self, atomic_counter_t, &atomic_counter_data, counter);
+  return ULL2NUM(atomic_counter_get_and_reset(counter));
 }
 
 static void init_counter(VALUE rb_mCAtomics) {
   VALUE rb_cAtomicCounter =
       rb_define_class_under(rb_mCAtomics, "AtomicCounter", rb_cObject);
   rb_define_alloc_func(rb_cAtomicCounter, rb_atomic_counter_alloc);
+  rb_define_method(rb_cAtomicCounter, "initialize",
+                   rb_atomic_counter_initialize, -1);
   rb_define_method(rb_cAtomicCounter, "increment", rb_atomic_counter_increment,
                    0);
   rb_define_method(rb_cAtomicCounter, "read", rb_atomic_counter_read, 0);
+  rb_define_method(rb_cAtomicCounter, "add", rb_atomic_counter_add, 1);
+  rb_define_method(rb_cAtomicCounter, "sub", rb_atomic_counter_sub, 1);
+  rb_define_method(rb_cAtomicCounter, "swap", rb_atomic_counter_swap, 1);
+  rb_define_method(rb_cAtomicCounter, "compare_and_set",
+                   rb_atomic_counter_compare_and_set, 2);
+  rb_define_method(rb_cAtomicCounter, "fetch_max", rb_atomic_counter_fetch_max,
+                   1);
+  rb_define_method(rb_cAtomicCounter, "fetch_min", rb_atomic_counter_fetch_min,
+                   1);
+  rb_define_method(rb_cAtomicCounter, "get_and_reset",
+                   rb_atomic_counter_get_and_reset, 0);
 }
@@ -2,6 +2,15 @@
 
 require "mkmf"
 
+# rust-atomics reads Strings and immediates using the object layout
+# of 64-bit MRI >= 3.2 (see rust-atomics/src/rvalue.rs)
+if Gem::Version.new(RUBY_VERSION) < Gem::Version.new("3.2.0")
+  abort "c_atomics requires Ruby >= 3.2, got #{RUBY_VERSION}"
+end
+if [nil].pack("p").bytesize != 8
+  abort "c_atomics requires a 64-bit Ruby"
+end
+
 # Makes all symbols private by default to avoid unintended conflict
 # with other gems. To explicitly export symbols you can use RUBY_FUNC_EXPORTED
 # selectively, or entirely remove this flag.
@@ -11,4 +20,7 @@ rust_atomics_path = File.expand_path("../../../../rust-atomics", __FILE__)
 $INCFLAGS << " -I#{rust_atomics_path}"
 $LDFLAGS << " -L#{rust_atomics_path}/target/release -lrust_atomics"
 
+# Must match the `race-detector` cargo feature of the linked Rust library
+append_cflags("-DRUST_ATOMICS_RACE_DETECTOR") if ENV["RUST_ATOMICS_RACE_DETECTOR"]
+
 create_makefile("c_atomics/c_atomics")
@@ -3,6 +3,7 @@
 
 void rb_concurrent_hash_map_mark(void *);
 void rb_concurrent_hash_map_free(void *);
+extern const rb_data_type_t mpmc_queue_data;
 
 const rb_data_type_t concurrent_hash_map_data = {
     .function = {.dfree = rb_concurrent_hash_map_free,
@@ -24,12 +25,27 @@ VALUE rb_concurrent_hash_map_alloc(VALUE klass) {
   TypedData_Make_Struct0(obj, klass, concurrent_hash_map_t,
                          CONCURRENT_HASH_MAP_SIZE, &concurrent_hash_map_data,
                          hashmap);
-  concurrent_hash_map_init(hashmap);
+  concurrent_hash_map_alloc(hashmap);
   VALUE rb_cRactor = rb_const_get(rb_cObject, rb_intern("Ractor"));
   rb_funcall(rb_cRactor, rb_intern("make_shareable"), 1, obj);
   return obj;
 }
 
+VALUE rb_concurrent_hash_map_initialize(int argc, VALUE *argv, VALUE self) {
+  VALUE capacity, shards_count;
+  rb_scan_args(argc, argv, "02", &capacity, &shards_count);
+  size_t shards = NIL_P(shards_count) ? 0 : NUM2SIZET(shards_count);
+  if (!NIL_P(shards_count) && (shards < 2 || (shards & (shards - 1)) != 0)) {
+    rb_raise(rb_eArgError, "shards count must be a power of two above 1");
+  }
+  concurrent_hash_map_t *hashmap;
+  TypedData_Get_Struct(self, concurrent_hash_map_t, &concurrent_hash_map_data,
+                       hashmap);
+  concurrent_hash_map_init(hashmap, NIL_P(capacity) ? 0 : NUM2SIZET(capacity),
+                           shards);
+  return Qnil;
+}
+
 VALUE rb_concurrent_hash_map_get(VALUE self, VALUE key) {
   concurrent_hash_map_t *hashmap;
   TypedData_Get_Struct(self, concurrent_hash_map_t, &concurrent_hash_map_data,
@@ -37,39 +53,555 @@ VALUE rb_concurrent_hash_map_get(VALUE self, VALUE key) {
   return concurrent_hash_map_get(hashmap, key, Qnil);
 }
 
+// Events are built after the map is updated and outside of its locks,
+// allocating them may trigger GC that marks the map. For the same reason two
+// writers updating the same key may publish their events in the opposite
+// order, only events of a single writer are ordered.
+static void rb_concurrent_hash_map_notify(concurrent_hash_map_t *hashmap,
+                                          VALUE key, VALUE old_value,
+                                          VALUE new_value) {
+  if (!concurrent_hash_map_has_subscribers(hashmap)) {
+    return;
+  }
+  VALUE event = rb_ary_new_from_args(
+      3, key, old_value == Qundef ? Qnil : old_value, new_value);
+  rb_obj_freeze(event);
+  concurrent_hash_map_publish(hashmap, event);
+}
+
+// For writers that report a missing key as CAtomics::UNDEFINED
+static void rb_concurrent_hash_map_notify_upsert(
+    concurrent_hash_map_t *hashmap, VALUE key, VALUE old_value,
+    VALUE new_value, VALUE undefined) {
+  if (old_value == undefined && new_value == undefined) {
+    return;
+  }
+  rb_concurrent_hash_map_notify(hashmap, key,
+                                old_value == undefined ? Qundef : old_value,
+                                new_value == undefined ? Qnil : new_value);
+}
+
+// Blocks that run under a shard lock must not longjmp over Rust frames:
+// rb_protect catches raise, break and throw, Rust then releases the lock
+// and the jump is resumed with rb_jump_tag.
+static VALUE rb_concurrent_hash_map_yield_protected(VALUE value, int *state) {
+  return rb_protect(rb_yield, value, state);
+}
+
 VALUE rb_concurrent_hash_map_set(VALUE self, VALUE key, VALUE value) {
   concurrent_hash_map_t *hashmap;
   TypedData_Get_Struct(self, concurrent_hash_map_t, &concurrent_hash_map_data,
                        hashmap);
-  concurrent_hash_map_set(hashmap, key, value);
+  VALUE old_value = concurrent_hash_map_swap(hashmap, key, value, Qundef);
+  rb_concurrent_hash_map_notify(hashmap, key, old_value, value);
   return Qnil;
 }
 
+// Removed pairs are only collected for subscribers, the map is cleared
+// shard by shard and a bigger buffer is taken if a shard doesn't fit.
 VALUE rb_concurrent_hash_map_clear(VALUE self) {
   concurrent_hash_map_t *hashmap;
   TypedData_Get_Struct(self, concurrent_hash_map_t, &concurrent_hash_map_data,
                        hashmap);
-  concurrent_hash_map_clear(hashmap);
+  size_t len;
+  if (!concurrent_hash_map_has_subscribers(hashmap)) {
+    concurrent_hash_map_clear(hashmap, NULL, 0, &len);
+    return Qnil;
+  }
+  size_t capa = concurrent_hash_map_size(hashmap) + 1;
+  bool done = false;
+  while (!done) {
+    VALUE tmp;
+    VALUE *removed = rb_alloc_tmp_buffer(&tmp, sizeof(VALUE) * 2 * capa);
+    done = concurrent_hash_map_clear(hashmap, removed, capa, &len);
+    for (size_t i = 0; i < len; i++) {
+      rb_concurrent_hash_map_notify(hashmap, removed[2 * i],
+                                    removed[2 * i + 1], Qnil);
+    }
+    rb_free_tmp_buffer(&tmp);
+    capa *= 2;
+  }
   return Qnil;
 }
 
+VALUE rb_concurrent_hash_map_delete(VALUE self, VALUE key) {
+  concurrent_hash_map_t *hashmap;
+  TypedData_Get_Struct(self, concurrent_hash_map_t, &concurrent_hash_map_data,
+                       hashmap);
+  VALUE old_value = concurrent_hash_map_delete(hashmap, key, Qundef);
+  if (old_value == Qundef) {
+    return Qnil;
+  }
+  rb_concurrent_hash_map_notify(hashmap, key, old_value, Qnil);
+  return old_value;
+}
+
+VALUE rb_concurrent_hash_map_key_p(VALUE self, VALUE key) {
+  concurrent_hash_map_t *hashmap;
+  TypedData_Get_Struct(self, concurrent_hash_map_t, &concurrent_hash_map_data,
+                       hashmap);
+  return concurrent_hash_map_contains_key(hashmap, key) ? Qtrue : Qfalse;
+}
+
+VALUE rb_concurrent_hash_map_size(VALUE self) {
+  concurrent_hash_map_t *hashmap;
+  TypedData_Get_Struct(self, concurrent_hash_map_t, &concurrent_hash_map_data,
+                       hashmap);
+  return SIZET2NUM(concurrent_hash_map_size(hashmap));
+}
+
+VALUE rb_concurrent_hash_map_empty_p(VALUE self) {
+  concurrent_hash_map_t *hashmap;
+  TypedData_Get_Struct(self, concurrent_hash_map_t, &concurrent_hash_map_data,
+                       hashmap);
+  return concurrent_hash_map_is_empty(hashmap) ? Qtrue : Qfalse;
+}
+
+VALUE rb_concurrent_hash_map_replace_if(VALUE self, VALUE key, VALUE expected,
+                                        VALUE new_value) {
+  concurrent_hash_map_t *hashmap;
+  TypedData_Get_Struct(self, concurrent_hash_map_t, &concurrent_hash_map_data,
+                       hashmap);
+  if (!concurrent_hash_map_replace_if(hashmap, key, expected, new_value)) {
+    return Qfalse;
+  }
+  rb_concurrent_hash_map_notify(hashmap, key, expected, new_value);
+  return Qtrue;
+}
+
+VALUE rb_concurrent_hash_map_delete_if(VALUE self, VALUE key, VALUE expected) {
+  concurrent_hash_map_t *hashmap;
+  TypedData_Get_Struct(self, concurrent_hash_map_t, &concurrent_hash_map_data,
+                       hashmap);
+  if (!concurrent_hash_map_delete_if(hashmap, key, expected)) {
+    return Qfalse;
+  }
+  rb_concurrent_hash_map_notify(hashmap, key, expected, Qnil);
+  return Qtrue;
+}
+
+static uint8_t rb_concurrent_hash_map_tx_op_kind(VALUE op) {
+  Check_Type(op, T_ARRAY);
+  VALUE name = RARRAY_LEN(op) > 0 ? RARRAY_AREF(op, 0) : Qnil;
+  long arity;
+  uint8_t kind;
+  if (name == ID2SYM(rb_intern("get"))) {
+    kind = CONCURRENT_HASH_MAP_TX_GET;
+    arity = 2;
+  } else if (name == ID2SYM(rb_intern("set"))) {
+    kind = CONCURRENT_HASH_MAP_TX_SET;
+    arity = 3;
+  } else if (name == ID2SYM(rb_intern("delete"))) {
+    kind = CONCURRENT_HASH_MAP_TX_DELETE;
+    arity = 2;
+  } else {
+    rb_raise(rb_eArgError, "op must start with :get, :set or :delete");
+  }
+  if (RARRAY_LEN(op) != arity) {
+    rb_raise(rb_eArgError, "wrong number of op arguments");
+  }
+  return kind;
+}
+
+VALUE rb_concurrent_hash_map_transaction(VALUE self, VALUE ops) {
+  Check_Type(ops, T_ARRAY);
+  concurrent_hash_map_t *hashmap;
+  TypedData_Get_Struct(self, concurrent_hash_map_t, &concurrent_hash_map_data,
+                       hashmap);
+  long len = RARRAY_LEN(ops);
+  VALUE ops_tmp, pre_images_tmp;
+  concurrent_hash_map_tx_op_t *tx_ops = rb_alloc_tmp_buffer(
+      &ops_tmp, sizeof(concurrent_hash_map_tx_op_t) * len);
+  for (long i = 0; i < len; i++) {
+    VALUE op = RARRAY_AREF(ops, i);
+    tx_ops[i].kind = rb_concurrent_hash_map_tx_op_kind(op);
+    tx_ops[i].key = RARRAY_AREF(op, 1);
+    tx_ops[i].value = RARRAY_LEN(op) > 2 ? RARRAY_AREF(op, 2) : Qnil;
+  }
+  VALUE *pre_images =
+      rb_alloc_tmp_buffer(&pre_images_tmp, sizeof(VALUE) * len);
+  concurrent_hash_map_transaction(hashmap, tx_ops, len, pre_images, Qundef);
+
+  VALUE result = rb_ary_new_capa(len);
+  for (long i = 0; i < len; i++) {
+    concurrent_hash_map_tx_op_t op = tx_ops[i];
+    if (op.kind == CONCURRENT_HASH_MAP_TX_SET) {
+      rb_concurrent_hash_map_notify(hashmap, op.key, pre_images[i], op.value);
+    } else if (op.kind == CONCURRENT_HASH_MAP_TX_DELETE &&
+               pre_images[i] != Qundef) {
+      rb_concurrent_hash_map_notify(hashmap, op.key, pre_images[i], Qnil);
+    }
+    rb_ary_push(result, pre_images[i] == Qundef ? Qnil : pre_images[i]);
+  }
+  rb_free_tmp_buffer(&pre_images_tmp);
+  rb_free_tmp_buffer(&ops_tmp);
+  return result;
+}
+
+VALUE rb_concurrent_hash_map_set_many(VALUE self, VALUE keys, VALUE values) {
+  Check_Type(keys, T_ARRAY);
+  Check_Type(values, T_ARRAY);
+  if (RARRAY_LEN(keys) != RARRAY_LEN(values)) {
+    rb_raise(rb_eArgError, "keys and values must have the same length");
+  }
+  concurrent_hash_map_t *hashmap;
+  TypedData_Get_Struct(self, concurrent_hash_map_t, &concurrent_hash_map_data,
+                       hashmap);
+  long len = RARRAY_LEN(keys);
+  VALUE tmp;
+  VALUE *old_values = rb_alloc_tmp_buffer(&tmp, sizeof(VALUE) * len);
+  concurrent_hash_map_set_many(hashmap, RARRAY_CONST_PTR(keys),
+                               RARRAY_CONST_PTR(values), len, old_values,
+                               Qundef);
+  for (long i = 0; i < len; i++) {
+    rb_concurrent_hash_map_notify(hashmap, RARRAY_AREF(keys, i), old_values[i],
+                                  RARRAY_AREF(values, i));
+  }
+  rb_free_tmp_buffer(&tmp);
+  return Qnil;
+}
+
+VALUE rb_concurrent_hash_map_get_many(VALUE self, VALUE keys) {
+  Check_Type(keys, T_ARRAY);
+  concurrent_hash_map_t *hashmap;
+  TypedData_Get_Struct(self, concurrent_hash_map_t, &concurrent_hash_map_data,
+                       hashmap);
+  long len = RARRAY_LEN(keys);
+  VALUE tmp;
+  VALUE *values = rb_alloc_tmp_buffer(&tmp, sizeof(VALUE) * len);
+  concurrent_hash_map_get_many(hashmap, RARRAY_CONST_PTR(keys), len, values,
+                               Qnil);
+  VALUE result = rb_ary_new_from_values(len, values);
+  rb_free_tmp_buffer(&tmp);
+  return result;
+}
+
+VALUE rb_concurrent_hash_map_delete_many(VALUE self, VALUE keys) {
+  Check_Type(keys, T_ARRAY);
+  concurrent_hash_map_t *hashmap;
+  TypedData_Get_Struct(self, concurrent_hash_map_t, &concurrent_hash_map_data,
+                       hashmap);
+  long len = RARRAY_LEN(keys);
+  VALUE tmp;
+  VALUE *old_values = rb_alloc_tmp_buffer(&tmp, sizeof(VALUE) * len);
+  size_t deleted = concurrent_hash_map_delete_many(
+      hashmap, RARRAY_CONST_PTR(keys), len, old_values, Qundef);
+  for (long i = 0; i < len; i++) {
+    if (old_values[i] != Qundef) {
+      rb_concurrent_hash_map_notify(hashmap, RARRAY_AREF(keys, i),
+                                    old_values[i], Qnil);
+    }
+  }
+  rb_free_tmp_buffer(&tmp);
+  return SIZET2NUM(deleted);
+}
+
+VALUE rb_concurrent_hash_map_subscribe(int argc, VALUE *argv, VALUE self) {
+  VALUE capacity;
+  rb_scan_args(argc, argv, "01", &capacity);
+  if (NIL_P(capacity)) {
+    capacity = INT2FIX(1024);
+  }
+  long capa = NUM2LONG(capacity);
+  if (capa < 2 || (capa & (capa - 1)) != 0) {
+    rb_raise(rb_eArgError, "capacity must be a power of two above 1");
+  }
+  concurrent_hash_map_t *hashmap;
+  TypedData_Get_Struct(self, concurrent_hash_map_t, &concurrent_hash_map_data,
+                       hashmap);
+  VALUE rb_mCAtomics = rb_const_get(rb_cObject, rb_intern("CAtomics"));
+  VALUE rb_cMpmcQueue = rb_const_get(rb_mCAtomics, rb_intern("MpmcQueue"));
+  VALUE queue_obj = rb_class_new_instance(1, &capacity, rb_cMpmcQueue);
+  mpmc_queue_t *queue;
+  TypedData_Get_Struct(queue_obj, mpmc_queue_t, &mpmc_queue_data, queue);
+  concurrent_hash_map_subscribe(hashmap, queue_obj, queue);
+  return queue_obj;
+}
+
+VALUE rb_concurrent_hash_map_unsubscribe(VALUE self, VALUE queue_obj) {
+  concurrent_hash_map_t *hashmap;
+  TypedData_Get_Struct(self, concurrent_hash_map_t, &concurrent_hash_map_data,
+                       hashmap);
+  return concurrent_hash_map_unsubscribe(hashmap, queue_obj) ? Qtrue : Qfalse;
+}
+
+VALUE rb_concurrent_hash_map_shrink_to_fit(VALUE self) {
+  concurrent_hash_map_t *hashmap;
+  TypedData_Get_Struct(self, concurrent_hash_map_t, &concurrent_hash_map_data,
+                       hashmap);
+  concurrent_hash_map_shrink_to_fit(hashmap);
+  return Qnil;
+}
+
+VALUE rb_concurrent_hash_map_memory_usage(VALUE self) {
+  concurrent_hash_map_t *hashmap;
+  TypedData_Get_Struct(self, concurrent_hash_map_t, &concurrent_hash_map_data,
+                       hashmap);
+  concurrent_hash_map_memory_usage_t usage =
+      concurrent_hash_map_memory_usage(hashmap);
+  VALUE report = rb_hash_new();
+  rb_hash_aset(report, ID2SYM(rb_intern("size")), SIZET2NUM(usage.size));
+  rb_hash_aset(report, ID2SYM(rb_intern("capacity")),
+               SIZET2NUM(usage.capacity));
+  rb_hash_aset(report, ID2SYM(rb_intern("shards")), SIZET2NUM(usage.shards));
+  rb_hash_aset(report, ID2SYM(rb_intern("bytes")), SIZET2NUM(usage.bytes));
+  return report;
+}
+
+VALUE rb_concurrent_hash_map_put_if_absent(VALUE self, VALUE key,
+                                           VALUE value) {
+  concurrent_hash_map_t *hashmap;
+  TypedData_Get_Struct(self, concurrent_hash_map_t, &concurrent_hash_map_data,
+                       hashmap);
+  VALUE old_value;
+  VALUE current = concurrent_hash_map_put_if_absent(hashmap, key, value,
+                                                    &old_value, Qundef);
+  if (old_value == Qundef) {
+    rb_concurrent_hash_map_notify(hashmap, key, Qundef, current);
+  }
+  return current;
+}
+
+VALUE rb_concurrent_hash_map_compute_if_absent(VALUE self, VALUE key) {
+  rb_need_block();
+  concurrent_hash_map_t *hashmap;
+  TypedData_Get_Struct(self, concurrent_hash_map_t, &concurrent_hash_map_data,
+                       hashmap);
+  VALUE old_value;
+  int state = 0;
+  VALUE current = concurrent_hash_map_compute_if_absent(
+      hashmap, key, rb_concurrent_hash_map_yield_protected, &old_value, Qundef,
+      &state);
+  if (state) {
+    rb_jump_tag(state);
+  }
+  if (old_value == Qundef) {
+    rb_concurrent_hash_map_notify(hashmap, key, Qundef, current);
+  }
+  return current;
+}
+
+typedef struct {
+  VALUE tmp;
+  VALUE *items;
+  size_t len;
+} concurrent_hash_map_snapshot_t;
+
+// Copies VALUEs into a GC-visible temporary buffer, so that objects removed
+// from the map by other Ractors stay alive while we yield them.
+static void rb_concurrent_hash_map_take_snapshot(
+    concurrent_hash_map_t *hashmap, concurrent_hash_map_snapshot_t *snapshot,
+    size_t (*f)(const concurrent_hash_map_t *, unsigned long *, size_t),
+    size_t width) {
+  size_t capa = concurrent_hash_map_size(hashmap) + 1;
+  while (true) {
+    snapshot->items =
+        rb_alloc_tmp_buffer(&snapshot->tmp, sizeof(VALUE) * width * capa);
+    size_t len = f(hashmap, snapshot->items, capa);
+    if (len <= capa) {
+      snapshot->len = len;
+      return;
+    }
+    rb_free_tmp_buffer(&snapshot->tmp);
+    capa = len;
+  }
+}
+
+VALUE rb_concurrent_hash_map_each_pair(VALUE self) {
+  RETURN_ENUMERATOR(self, 0, 0);
+  concurrent_hash_map_t *hashmap;
+  TypedData_Get_Struct(self, concurrent_hash_map_t, &concurrent_hash_map_data,
+                       hashmap);
+  concurrent_hash_map_snapshot_t snapshot;
+  rb_concurrent_hash_map_take_snapshot(hashmap, &snapshot,
+                                       concurrent_hash_map_snapshot, 2);
+  for (size_t i = 0; i < snapshot.len; i++) {
+    rb_yield_values(2, snapshot.items[2 * i], snapshot.items[2 * i + 1]);
+  }
+  rb_free_tmp_buffer(&snapshot.tmp);
+  return self;
+}
+
+VALUE rb_concurrent_hash_map_keys(VALUE self) {
+  concurrent_hash_map_t *hashmap;
+  TypedData_Get_Struct(self, concurrent_hash_map_t, &concurrent_hash_map_data,
+                       hashmap);
+  concurrent_hash_map_snapshot_t snapshot;
+  rb_concurrent_hash_map_take_snapshot(hashmap, &snapshot,
+                                       concurrent_hash_map_keys, 1);
+  VALUE keys = rb_ary_new_from_values(snapshot.len, snapshot.items);
+  rb_free_tmp_buffer(&snapshot.tmp);
+  return keys;
+}
+
+VALUE rb_concurrent_hash_map_values(VALUE self) {
+  concurrent_hash_map_t *hashmap;
+  TypedData_Get_Struct(self, concurrent_hash_map_t, &concurrent_hash_map_data,
+                       hashmap);
+  concurrent_hash_map_snapshot_t snapshot;
+  rb_concurrent_hash_map_take_snapshot(hashmap, &snapshot,
+                                       concurrent_hash_map_values, 1);
+  VALUE values = rb_ary_new_from_values(snapshot.len, snapshot.items);
+  rb_free_tmp_buffer(&snapshot.tmp);
+  return values;
+}
+
+VALUE rb_concurrent_hash_map_to_h(VALUE self) {
+  concurrent_hash_map_t *hashmap;
+  TypedData_Get_Struct(self, concurrent_hash_map_t, &concurrent_hash_map_data,
+                       hashmap);
+  concurrent_hash_map_snapshot_t snapshot;
+  rb_concurrent_hash_map_take_snapshot(hashmap, &snapshot,
+                                       concurrent_hash_map_snapshot, 2);
+  VALUE hash = rb_hash_new();
+  for (size_t i = 0; i < snapshot.len; i++) {
+    rb_hash_aset(hash, snapshot.items[2 * i], snapshot.items[2 * i + 1]);
+  }
+  rb_free_tmp_buffer(&snapshot.tmp);
+  return hash;
+}
+
+// The merge happens in one call, it's only repeated with a bigger buffer if
+// `other` has grown and nothing was merged yet. Replaced values stay in a
+// GC-visible buffer until their events are published.
+VALUE rb_concurrent_hash_map_merge(VALUE self, VALUE other) {
+  concurrent_hash_map_t *hashmap;
+  TypedData_Get_Struct(self, concurrent_hash_map_t, &concurrent_hash_map_data,
+                       hashmap);
+  concurrent_hash_map_t *other_hashmap;
+  TypedData_Get_Struct(other, concurrent_hash_map_t,
+                       &concurrent_hash_map_data, other_hashmap);
+  size_t capa = concurrent_hash_map_size(other_hashmap) + 1;
+  while (true) {
+    VALUE tmp;
+    VALUE *merged = rb_alloc_tmp_buffer(&tmp, sizeof(VALUE) * 3 * capa);
+    size_t len =
+        concurrent_hash_map_merge(hashmap, other_hashmap, merged, capa, Qundef);
+    if (len <= capa) {
+      for (size_t i = 0; i < len; i++) {
+        rb_concurrent_hash_map_notify(hashmap, merged[3 * i],
+                                      merged[3 * i + 1], merged[3 * i + 2]);
+      }
+      rb_free_tmp_buffer(&tmp);
+      return self;
+    }
+    rb_free_tmp_buffer(&tmp);
+    capa = len;
+  }
+}
+
 VALUE rb_concurrent_hash_map_fetch_and_modify(VALUE self, VALUE key) {
   rb_need_block();
   concurrent_hash_map_t *hashmap;
   TypedData_Get_Struct(self, concurrent_hash_map_t, &concurrent_hash_map_data,
                        hashmap);
-  concurrent_hash_map_fetch_and_modify(hashmap, key, rb_yield);
+  VALUE old_value;
+  int state = 0;
+  VALUE new_value = concurrent_hash_map_fetch_and_modify(
+      hashmap, key, rb_concurrent_hash_map_yield_protected, &old_value, Qundef,
+      &state);
+  if (state) {
+    rb_jump_tag(state);
+  }
+  if (new_value != Qundef) {
+    rb_concurrent_hash_map_notify(hashmap, key, old_value, new_value);
+  }
   return Qnil;
 }
 
+VALUE rb_concurrent_hash_map_upsert(VALUE self, VALUE key) {
+  rb_need_block();
+  concurrent_hash_map_t *hashmap;
+  TypedData_Get_Struct(self, concurrent_hash_map_t, &concurrent_hash_map_data,
+                       hashmap);
+  VALUE rb_mCAtomics = rb_const_get(rb_cObject, rb_intern("CAtomics"));
+  VALUE undefined = rb_const_get(rb_mCAtomics, rb_intern("UNDEFINED"));
+  VALUE old_value;
+  int state = 0;
+  VALUE value = concurrent_hash_map_upsert(
+      hashmap, key, undefined, rb_concurrent_hash_map_yield_protected,
+      &old_value, &state);
+  if (state) {
+    rb_jump_tag(state);
+  }
+  rb_concurrent_hash_map_notify_upsert(hashmap, key, old_value, value,
+                                       undefined);
+  return value == undefined ? Qnil : value;
+}
+
+// Unlike upsert, the block runs outside of map locks and may use the map,
+// it is re-run if the value changes before its result is committed.
+VALUE rb_concurrent_hash_map_compute(VALUE self, VALUE key) {
+  rb_need_block();
+  concurrent_hash_map_t *hashmap;
+  TypedData_Get_Struct(self, concurrent_hash_map_t, &concurrent_hash_map_data,
+                       hashmap);
+  VALUE rb_mCAtomics = rb_const_get(rb_cObject, rb_intern("CAtomics"));
+  VALUE undefined = rb_const_get(rb_mCAtomics, rb_intern("UNDEFINED"));
+  VALUE old_value;
+  VALUE value = concurrent_hash_map_compute(hashmap, key, undefined, rb_yield,
+                                            &old_value);
+  rb_concurrent_hash_map_notify_upsert(hashmap, key, old_value, value,
+                                       undefined);
+  return value == undefined ? Qnil : value;
+}
+
 static void init_hashmap(VALUE rb_mCAtomics) {
   VALUE rb_cConcurrentHashMap =
       rb_define_class_under(rb_mCAtomics, "ConcurrentHashMap", rb_cObject);
   rb_define_alloc_func(rb_cConcurrentHashMap, rb_concurrent_hash_map_alloc);
+  rb_define_method(rb_cConcurrentHashMap, "initialize",
+                   rb_concurrent_hash_map_initialize, -1);
   rb_define_method(rb_cConcurrentHashMap, "get", rb_concurrent_hash_map_get, 1);
   rb_define_method(rb_cConcurrentHashMap, "set", rb_concurrent_hash_map_set, 2);
   rb_define_method(rb_cConcurrentHashMap, "clear", rb_concurrent_hash_map_clear,
                    0);
   rb_define_method(rb_cConcurrentHashMap, "fetch_and_modify",
                    rb_concurrent_hash_map_fetch_and_modify, 1);
+  rb_define_method(rb_cConcurrentHashMap, "delete",
+                   rb_concurrent_hash_map_delete, 1);
+  rb_define_method(rb_cConcurrentHashMap, "key?", rb_concurrent_hash_map_key_p,
+                   1);
+  rb_define_method(rb_cConcurrentHashMap, "size", rb_concurrent_hash_map_size,
+                   0);
+  rb_define_method(rb_cConcurrentHashMap, "empty?",
+                   rb_concurrent_hash_map_empty_p, 0);
+  rb_define_method(rb_cConcurrentHashMap, "put_if_absent",
+                   rb_concurrent_hash_map_put_if_absent, 2);
+  rb_define_method(rb_cConcurrentHashMap, "compute_if_absent",
+                   rb_concurrent_hash_map_compute_if_absent, 1);
+  rb_define_method(rb_cConcurrentHashMap, "each_pair",
+                   rb_concurrent_hash_map_each_pair, 0);
+  rb_define_method(rb_cConcurrentHashMap, "keys", rb_concurrent_hash_map_keys,
+                   0);
+  rb_define_method(rb_cConcurrentHashMap, "values",
+                   rb_concurrent_hash_map_values, 0);
+  rb_define_method(rb_cConcurrentHashMap, "to_h", rb_concurrent_hash_map_to_h,
+                   0);
+  rb_define_method(rb_cConcurrentHashMap, "upsert",
+                   rb_concurrent_hash_map_upsert, 1);
+  rb_define_method(rb_cConcurrentHashMap, "compute",
+                   rb_concurrent_hash_map_compute, 1);
+  rb_define_method(rb_cConcurrentHashMap, "replace_if",
+                   rb_concurrent_hash_map_replace_if, 3);
+  rb_define_method(rb_cConcurrentHashMap, "delete_if",
+                   rb_concurrent_hash_map_delete_if, 2);
+  rb_define_method(rb_cConcurrentHashMap, "set_many",
+                   rb_concurrent_hash_map_set_many, 2);
+  rb_define_method(rb_cConcurrentHashMap, "get_many",
+                   rb_concurrent_hash_map_get_many, 1);
+  rb_define_method(rb_cConcurrentHashMap, "delete_many",
+                   rb_concurrent_hash_map_delete_many, 1);
+  rb_define_method(rb_cConcurrentHashMap, "merge", rb_concurrent_hash_map_merge,
+                   1);
+  rb_define_method(rb_cConcurrentHashMap, "transaction",
+                   rb_concurrent_hash_map_transaction, 1);
+  rb_define_method(rb_cConcurrentHashMap, "subscribe",
+                   rb_concurrent_hash_map_subscribe, -1);
+  rb_define_method(rb_cConcurrentHashMap, "unsubscribe",
+                   rb_concurrent_hash_map_unsubscribe, 1);
+  rb_define_method(rb_cConcurrentHashMap, "shrink_to_fit",
+                   rb_concurrent_hash_map_shrink_to_fit, 0);
+  rb_define_method(rb_cConcurrentHashMap, "memory_usage",
+                   rb_concurrent_hash_map_memory_usage, 0);
 }
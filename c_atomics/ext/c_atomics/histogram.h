@@ -0,0 +1,98 @@
+#include "rust-atomics.h"
+#include <ruby.h>
+
+void rb_histogram_free(void *);
+
+const rb_data_type_t histogram_data = {
+    .function = {.dfree = rb_histogram_free},
+    .flags = RUBY_TYPED_FROZEN_SHAREABLE};
+
+void rb_histogram_free(void *ptr) {
+  histogram_t *histogram = ptr;
+  histogram_drop(histogram);
+}
+
+VALUE rb_histogram_alloc(VALUE klass) {
+  histogram_t *histogram;
+  TypedData_Make_Struct0(obj, klass, histogram_t, HISTOGRAM_SIZE,
+                         &histogram_data, histogram);
+  histogram_alloc(histogram);
+  VALUE rb_cRactor = rb_const_get(rb_cObject, rb_intern("Ractor"));
+  rb_funcall(rb_cRactor, rb_intern("make_shareable"), 1, obj);
+  return obj;
+}
+
+VALUE rb_histogram_initialize(VALUE self) {
+  histogram_t *histogram;
+  TypedData_Get_Struct(self, histogram_t, &histogram_data, histogram);
+  histogram_init(histogram);
+  return Qnil;
+}
+
+VALUE rb_histogram_record(VALUE self, VALUE value) {
+  histogram_t *histogram;
+  TypedData_Get_Struct(self, histogram_t, &histogram_data, histogram);
+  histogram_record(histogram, NUM2ULL(value));
+  return Qnil;
+}
+
+VALUE rb_histogram_count(VALUE self) {
+  histogram_t *histogram;
+  TypedData_Get_Struct(self, histogram_t, &histogram_data, histogram);
+  return ULL2NUM(histogram_count(histogram));
+}
+
+VALUE rb_histogram_sum(VALUE self) {
+  histogram_t *histogram;
+  TypedData_Get_Struct(self, histogram_t, &histogram_data, histogram);
+  return ULL2NUM(histogram_sum(histogram));
+}
+
+VALUE rb_histogram_min(VALUE self) {
+  histogram_t *histogram;
+  TypedData_Get_Struct(self, histogram_t, &histogram_data, histogram);
+  return ULL2NUM(histogram_min(histogram));
+}
+
+VALUE rb_histogram_max(VALUE self) {
+  histogram_t *histogram;
+  TypedData_Get_Struct(self, histogram_t, &histogram_data, histogram);
+  return ULL2NUM(histogram_max(histogram));
+}
+
+VALUE rb_histogram_percentile(VALUE self, VALUE percentile) {
+  histogram_t *histogram;
+  TypedData_Get_Struct(self, histogram_t, &histogram_data, histogram);
+  return ULL2NUM(histogram_percentile(histogram, NUM2DBL(percentile)));
+}
+
+VALUE rb_histogram_merge(VALUE self, VALUE other) {
+  histogram_t *histogram;
+  TypedData_Get_Struct(self, histogram_t, &histogram_data, histogram);
+  histogram_t *other_histogram;
+  TypedData_Get_Struct(other, histogram_t, &histogram_data, other_histogram);
+  histogram_merge(histogram, other_histogram);
+  return Qnil;
+}
+
+VALUE rb_histogram_reset(VALUE self) {
+  histogram_t *histogram;
+  TypedData_Get_Struct(self, histogram_t, &histogram_data, histogram);
+  histogram_reset(histogram);
+  return Qnil;
+}
+
+static void init_histogram(VALUE rb_mCAtomics) {
+  VALUE rb_cHistogram =
+      rb_define_class_under(rb_mCAtomics, "Histogram", rb_cObject);
+  rb_define_alloc_func(rb_cHistogram, rb_histogram_alloc);
+  rb_define_method(rb_cHistogram, "initialize", rb_histogram_initialize, 0);
+  rb_define_method(rb_cHistogram, "record", rb_histogram_record, 1);
+  rb_define_method(rb_cHistogram, "count", rb_histogram_count, 0);
+  rb_define_method(rb_cHistogram, "sum", rb_histogram_sum, 0);
+  rb_define_method(rb_cHistogram, "min", rb_histogram_min, 0);
+  rb_define_method(rb_cHistogram, "max", rb_histogram_max, 0);
+  rb_define_method(rb_cHistogram, "percentile", rb_histogram_percentile, 1);
+  rb_define_method(rb_cHistogram, "merge", rb_histogram_merge, 1);
+  rb_define_method(rb_cHistogram, "reset", rb_histogram_reset, 0);
+}
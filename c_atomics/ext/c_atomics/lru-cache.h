@@ -0,0 +1,122 @@
+#include "rust-atomics.h"
+#include <ruby.h>
+
+void rb_concurrent_lru_cache_mark(void *);
+void rb_concurrent_lru_cache_free(void *);
+
+const rb_data_type_t concurrent_lru_cache_data = {
+    .function = {.dfree = rb_concurrent_lru_cache_free,
+                 .dmark = rb_concurrent_lru_cache_mark},
+    .flags = RUBY_TYPED_FROZEN_SHAREABLE};
+
+void rb_concurrent_lru_cache_free(void *ptr) {
+  concurrent_lru_cache_t *cache = ptr;
+  concurrent_lru_cache_drop(cache);
+}
+
+void rb_concurrent_lru_cache_mark(void *ptr) {
+  concurrent_lru_cache_t *cache = ptr;
+  concurrent_lru_cache_mark(cache, rb_gc_mark);
+}
+
+VALUE rb_concurrent_lru_cache_alloc(VALUE klass) {
+  concurrent_lru_cache_t *cache;
+  TypedData_Make_Struct0(obj, klass, concurrent_lru_cache_t,
+                         CONCURRENT_LRU_CACHE_SIZE, &concurrent_lru_cache_data,
+                         cache);
+  concurrent_lru_cache_alloc(cache);
+  VALUE rb_cRactor = rb_const_get(rb_cObject, rb_intern("Ractor"));
+  rb_funcall(rb_cRactor, rb_intern("make_shareable"), 1, obj);
+  return obj;
+}
+
+VALUE rb_concurrent_lru_cache_initialize(int argc, VALUE *argv, VALUE self) {
+  VALUE capacity, shards_count;
+  rb_scan_args(argc, argv, "11", &capacity, &shards_count);
+  long capa = NUM2LONG(capacity);
+  long shards = NIL_P(shards_count) ? 16 : NUM2LONG(shards_count);
+  if (capa < 1) {
+    rb_raise(rb_eArgError, "capacity must be positive");
+  }
+  if (shards < 1) {
+    rb_raise(rb_eArgError, "shards count must be positive");
+  }
+  concurrent_lru_cache_t *cache;
+  TypedData_Get_Struct(self, concurrent_lru_cache_t,
+                       &concurrent_lru_cache_data, cache);
+  concurrent_lru_cache_init(cache, capa, shards);
+  return Qnil;
+}
+
+VALUE rb_concurrent_lru_cache_get(VALUE self, VALUE key) {
+  concurrent_lru_cache_t *cache;
+  TypedData_Get_Struct(self, concurrent_lru_cache_t,
+                       &concurrent_lru_cache_data, cache);
+  return concurrent_lru_cache_get(cache, key, Qnil);
+}
+
+VALUE rb_concurrent_lru_cache_set(VALUE self, VALUE key, VALUE value) {
+  concurrent_lru_cache_t *cache;
+  TypedData_Get_Struct(self, concurrent_lru_cache_t,
+                       &concurrent_lru_cache_data, cache);
+  concurrent_lru_cache_set(cache, key, value);
+  return Qnil;
+}
+
+VALUE rb_concurrent_lru_cache_delete(VALUE self, VALUE key) {
+  concurrent_lru_cache_t *cache;
+  TypedData_Get_Struct(self, concurrent_lru_cache_t,
+                       &concurrent_lru_cache_data, cache);
+  return concurrent_lru_cache_delete(cache, key, Qnil);
+}
+
+VALUE rb_concurrent_lru_cache_size(VALUE self) {
+  concurrent_lru_cache_t *cache;
+  TypedData_Get_Struct(self, concurrent_lru_cache_t,
+                       &concurrent_lru_cache_data, cache);
+  return SIZET2NUM(concurrent_lru_cache_size(cache));
+}
+
+VALUE rb_concurrent_lru_cache_clear(VALUE self) {
+  concurrent_lru_cache_t *cache;
+  TypedData_Get_Struct(self, concurrent_lru_cache_t,
+                       &concurrent_lru_cache_data, cache);
+  concurrent_lru_cache_clear(cache);
+  return Qnil;
+}
+
+VALUE rb_concurrent_lru_cache_hits(VALUE self) {
+  concurrent_lru_cache_t *cache;
+  TypedData_Get_Struct(self, concurrent_lru_cache_t,
+                       &concurrent_lru_cache_data, cache);
+  return ULL2NUM(concurrent_lru_cache_hits(cache));
+}
+
+VALUE rb_concurrent_lru_cache_misses(VALUE self) {
+  concurrent_lru_cache_t *cache;
+  TypedData_Get_Struct(self, concurrent_lru_cache_t,
+                       &concurrent_lru_cache_data, cache);
+  return ULL2NUM(concurrent_lru_cache_misses(cache));
+}
+
+static void init_lru_cache(VALUE rb_mCAtomics) {
+  VALUE rb_cConcurrentLruCache =
+      rb_define_class_under(rb_mCAtomics, "ConcurrentLruCache", rb_cObject);
+  rb_define_alloc_func(rb_cConcurrentLruCache, rb_concurrent_lru_cache_alloc);
+  rb_define_method(rb_cConcurrentLruCache, "initialize",
+                   rb_concurrent_lru_cache_initialize, -1);
+  rb_define_method(rb_cConcurrentLruCache, "get", rb_concurrent_lru_cache_get,
+                   1);
+  rb_define_method(rb_cConcurrentLruCache, "set", rb_concurrent_lru_cache_set,
+                   2);
+  rb_define_method(rb_cConcurrentLruCache, "delete",
+                   rb_concurrent_lru_cache_delete, 1);
+  rb_define_method(rb_cConcurrentLruCache, "size",
+                   rb_concurrent_lru_cache_size, 0);
+  rb_define_method(rb_cConcurrentLruCache, "clear",
+                   rb_concurrent_lru_cache_clear, 0);
+  rb_define_method(rb_cConcurrentLruCache, "hits",
+                   rb_concurrent_lru_cache_hits, 0);
+  rb_define_method(rb_cConcurrentLruCache, "misses",
+                   rb_concurrent_lru_cache_misses, 0);
+}
@@ -0,0 +1,155 @@
+#include "rust-atomics.h"
+#include <ruby.h>
+
+void rb_metrics_registry_free(void *);
+
+const rb_data_type_t metrics_registry_data = {
+    .function = {.dfree = rb_metrics_registry_free},
+    .flags = RUBY_TYPED_FROZEN_SHAREABLE};
+
+void rb_metrics_registry_free(void *ptr) {
+  metrics_registry_t *registry = ptr;
+  metrics_registry_drop(registry);
+}
+
+VALUE rb_metrics_registry_alloc(VALUE klass) {
+  metrics_registry_t *registry;
+  TypedData_Make_Struct0(obj, klass, metrics_registry_t, METRICS_REGISTRY_SIZE,
+                         &metrics_registry_data, registry);
+  metrics_registry_init(registry);
+  VALUE rb_cRactor = rb_const_get(rb_cObject, rb_intern("Ractor"));
+  rb_funcall(rb_cRactor, rb_intern("make_shareable"), 1, obj);
+  return obj;
+}
+
+#define RSTRING_BYTES(str) (const uint8_t *)RSTRING_PTR(str), RSTRING_LEN(str)
+
+VALUE rb_metrics_registry_register_counter(VALUE self, VALUE name, VALUE help) {
+  metrics_registry_t *registry;
+  TypedData_Get_Struct(self, metrics_registry_t, &metrics_registry_data,
+                       registry);
+  StringValue(name);
+  StringValue(help);
+  if (!metrics_registry_register_counter(registry, RSTRING_BYTES(name),
+                                         RSTRING_BYTES(help))) {
+    rb_raise(rb_eArgError, "can't register counter %s",
+             StringValueCStr(name));
+  }
+  return Qnil;
+}
+
+VALUE rb_metrics_registry_register_gauge(VALUE self, VALUE name, VALUE help) {
+  metrics_registry_t *registry;
+  TypedData_Get_Struct(self, metrics_registry_t, &metrics_registry_data,
+                       registry);
+  StringValue(name);
+  StringValue(help);
+  if (!metrics_registry_register_gauge(registry, RSTRING_BYTES(name),
+                                       RSTRING_BYTES(help))) {
+    rb_raise(rb_eArgError, "can't register gauge %s", StringValueCStr(name));
+  }
+  return Qnil;
+}
+
+VALUE rb_metrics_registry_register_histogram(VALUE self, VALUE name,
+                                             VALUE help) {
+  metrics_registry_t *registry;
+  TypedData_Get_Struct(self, metrics_registry_t, &metrics_registry_data,
+                       registry);
+  StringValue(name);
+  StringValue(help);
+  if (!metrics_registry_register_histogram(registry, RSTRING_BYTES(name),
+                                           RSTRING_BYTES(help))) {
+    rb_raise(rb_eArgError, "can't register histogram %s",
+             StringValueCStr(name));
+  }
+  return Qnil;
+}
+
+VALUE rb_metrics_registry_counter_add(VALUE self, VALUE name, VALUE delta) {
+  metrics_registry_t *registry;
+  TypedData_Get_Struct(self, metrics_registry_t, &metrics_registry_data,
+                       registry);
+  StringValue(name);
+  if (!metrics_registry_counter_add(registry, RSTRING_BYTES(name),
+                                    NUM2ULL(delta))) {
+    rb_raise(rb_eKeyError, "no counter named %s", StringValueCStr(name));
+  }
+  return Qnil;
+}
+
+VALUE rb_metrics_registry_gauge_set(VALUE self, VALUE name, VALUE value) {
+  metrics_registry_t *registry;
+  TypedData_Get_Struct(self, metrics_registry_t, &metrics_registry_data,
+                       registry);
+  StringValue(name);
+  if (!metrics_registry_gauge_set(registry, RSTRING_BYTES(name),
+                                  NUM2DBL(value))) {
+    rb_raise(rb_eKeyError, "no gauge named %s", StringValueCStr(name));
+  }
+  return Qnil;
+}
+
+VALUE rb_metrics_registry_gauge_add(VALUE self, VALUE name, VALUE delta) {
+  metrics_registry_t *registry;
+  TypedData_Get_Struct(self, metrics_registry_t, &metrics_registry_data,
+                       registry);
+  StringValue(name);
+  if (!metrics_registry_gauge_add(registry, RSTRING_BYTES(name),
+                                  NUM2DBL(delta))) {
+    rb_raise(rb_eKeyError, "no gauge named %s", StringValueCStr(name));
+  }
+  return Qnil;
+}
+
+VALUE rb_metrics_registry_histogram_record(VALUE self, VALUE name,
+                                           VALUE value) {
+  metrics_registry_t *registry;
+  TypedData_Get_Struct(self, metrics_registry_t, &metrics_registry_data,
+                       registry);
+  StringValue(name);
+  if (!metrics_registry_histogram_record(registry, RSTRING_BYTES(name),
+                                         NUM2ULL(value))) {
+    rb_raise(rb_eKeyError, "no histogram named %s", StringValueCStr(name));
+  }
+  return Qnil;
+}
+
+VALUE rb_metrics_registry_render(VALUE self) {
+  metrics_registry_t *registry;
+  TypedData_Get_Struct(self, metrics_registry_t, &metrics_registry_data,
+                       registry);
+  size_t capa = 4096;
+  while (true) {
+    VALUE out = rb_str_buf_new(capa);
+    size_t len =
+        metrics_registry_render(registry, (uint8_t *)RSTRING_PTR(out), capa);
+    if (len <= capa) {
+      rb_str_set_len(out, len);
+      return out;
+    }
+    capa = len;
+  }
+}
+
+static void init_metrics_registry(VALUE rb_mCAtomics) {
+  VALUE rb_cMetricsRegistry =
+      rb_define_class_under(rb_mCAtomics, "MetricsRegistry", rb_cObject);
+  rb_define_alloc_func(rb_cMetricsRegistry, rb_metrics_registry_alloc);
+  rb_define_method(rb_cMetricsRegistry, "register_counter",
+                   rb_metrics_registry_register_counter, 2);
+  rb_define_method(rb_cMetricsRegistry, "register_gauge",
+                   rb_metrics_registry_register_gauge, 2);
+  rb_define_method(rb_cMetricsRegistry, "register_histogram",
+                   rb_metrics_registry_register_histogram, 2);
+  rb_define_method(rb_cMetricsRegistry, "counter_add",
+                   rb_metrics_registry_counter_add, 2);
+  rb_define_method(rb_cMetricsRegistry, "gauge_set",
+                   rb_metrics_registry_gauge_set, 2);
+  rb_define_method(rb_cMetricsRegistry, "gauge_add",
+                   rb_metrics_registry_gauge_add, 2);
+  rb_define_method(rb_cMetricsRegistry, "histogram_record",
+                   rb_metrics_registry_histogram_record, 2);
+  rb_define_method(rb_cMetricsRegistry, "render", rb_metrics_registry_render,
+                   0);
+}
@@ -28,6 +28,20 @@ VALUE rb_plain_counter_read(VALUE self) {
   return LONG2FIX(plain_counter_read(counter));
 }
 
+#if defined(RUST_ATOMICS_RACE_DETECTOR)
+VALUE rb_plain_counter_races(VALUE self) {
+  plain_counter_t *counter;
+  TypedData_Get_Struct(self, plain_counter_t, &plain_counter_data, counter);
+  return ULL2NUM(plain_counter_races(counter));
+}
+
+VALUE rb_plain_counter_last_writer(VALUE self) {
+  plain_counter_t *counter;
+  TypedData_Get_Struct(self, plain_counter_t, &plain_counter_data, counter);
+  return ULL2NUM(plain_counter_last_writer(counter));
+}
+#endif
+
 static void init_plain_counter(VALUE rb_mCAtomics) {
   VALUE rb_cPlainCounter =
       rb_define_class_under(rb_mCAtomics, "PlainCounter", rb_cObject);
@@ -35,4 +49,9 @@ static void init_plain_counter(VALUE rb_mCAtomics) {
   rb_define_method(rb_cPlainCounter, "increment", rb_plain_counter_increment,
                    0);
   rb_define_method(rb_cPlainCounter, "read", rb_plain_counter_read, 0);
+#if defined(RUST_ATOMICS_RACE_DETECTOR)
+  rb_define_method(rb_cPlainCounter, "races", rb_plain_counter_races, 0);
+  rb_define_method(rb_cPlainCounter, "last_writer",
+                   rb_plain_counter_last_writer, 0);
+#endif
 }
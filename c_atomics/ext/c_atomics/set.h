@@ -0,0 +1,110 @@
+#include "rust-atomics.h"
+#include <ruby.h>
+
+void rb_concurrent_set_mark(void *);
+void rb_concurrent_set_free(void *);
+
+const rb_data_type_t concurrent_set_data = {
+    .function = {.dfree = rb_concurrent_set_free,
+                 .dmark = rb_concurrent_set_mark},
+    .flags = RUBY_TYPED_FROZEN_SHAREABLE};
+
+void rb_concurrent_set_free(void *ptr) {
+  concurrent_set_t *set = ptr;
+  concurrent_set_drop(set);
+}
+
+void rb_concurrent_set_mark(void *ptr) {
+  concurrent_set_t *set = ptr;
+  concurrent_set_mark(set, rb_gc_mark);
+}
+
+VALUE rb_concurrent_set_alloc(VALUE klass) {
+  concurrent_set_t *set;
+  TypedData_Make_Struct0(obj, klass, concurrent_set_t, CONCURRENT_SET_SIZE,
+                         &concurrent_set_data, set);
+  concurrent_set_init(set);
+  VALUE rb_cRactor = rb_const_get(rb_cObject, rb_intern("Ractor"));
+  rb_funcall(rb_cRactor, rb_intern("make_shareable"), 1, obj);
+  return obj;
+}
+
+VALUE rb_concurrent_set_add(VALUE self, VALUE key) {
+  concurrent_set_t *set;
+  TypedData_Get_Struct(self, concurrent_set_t, &concurrent_set_data, set);
+  return concurrent_set_add(set, key) ? Qtrue : Qfalse;
+}
+
+VALUE rb_concurrent_set_delete(VALUE self, VALUE key) {
+  concurrent_set_t *set;
+  TypedData_Get_Struct(self, concurrent_set_t, &concurrent_set_data, set);
+  return concurrent_set_delete(set, key) ? Qtrue : Qfalse;
+}
+
+VALUE rb_concurrent_set_include_p(VALUE self, VALUE key) {
+  concurrent_set_t *set;
+  TypedData_Get_Struct(self, concurrent_set_t, &concurrent_set_data, set);
+  return concurrent_set_contains(set, key) ? Qtrue : Qfalse;
+}
+
+VALUE rb_concurrent_set_size(VALUE self) {
+  concurrent_set_t *set;
+  TypedData_Get_Struct(self, concurrent_set_t, &concurrent_set_data, set);
+  return SIZET2NUM(concurrent_set_size(set));
+}
+
+VALUE rb_concurrent_set_empty_p(VALUE self) {
+  concurrent_set_t *set;
+  TypedData_Get_Struct(self, concurrent_set_t, &concurrent_set_data, set);
+  return concurrent_set_is_empty(set) ? Qtrue : Qfalse;
+}
+
+VALUE rb_concurrent_set_clear(VALUE self) {
+  concurrent_set_t *set;
+  TypedData_Get_Struct(self, concurrent_set_t, &concurrent_set_data, set);
+  concurrent_set_clear(set);
+  return Qnil;
+}
+
+// Same as ConcurrentHashMap#keys, the snapshot lives in a GC-visible buffer
+VALUE rb_concurrent_set_to_a(VALUE self) {
+  concurrent_set_t *set;
+  TypedData_Get_Struct(self, concurrent_set_t, &concurrent_set_data, set);
+  size_t capa = concurrent_set_size(set) + 1;
+  while (true) {
+    VALUE tmp;
+    VALUE *keys = rb_alloc_tmp_buffer(&tmp, sizeof(VALUE) * capa);
+    size_t len = concurrent_set_snapshot(set, keys, capa);
+    if (len <= capa) {
+      VALUE result = rb_ary_new_from_values(len, keys);
+      rb_free_tmp_buffer(&tmp);
+      return result;
+    }
+    rb_free_tmp_buffer(&tmp);
+    capa = len;
+  }
+}
+
+VALUE rb_concurrent_set_each(VALUE self) {
+  RETURN_ENUMERATOR(self, 0, 0);
+  VALUE keys = rb_concurrent_set_to_a(self);
+  for (long i = 0; i < RARRAY_LEN(keys); i++) {
+    rb_yield(RARRAY_AREF(keys, i));
+  }
+  return self;
+}
+
+static void init_set(VALUE rb_mCAtomics) {
+  VALUE rb_cConcurrentSet =
+      rb_define_class_under(rb_mCAtomics, "ConcurrentSet", rb_cObject);
+  rb_define_alloc_func(rb_cConcurrentSet, rb_concurrent_set_alloc);
+  rb_define_method(rb_cConcurrentSet, "add", rb_concurrent_set_add, 1);
+  rb_define_method(rb_cConcurrentSet, "delete", rb_concurrent_set_delete, 1);
+  rb_define_method(rb_cConcurrentSet, "include?", rb_concurrent_set_include_p,
+                   1);
+  rb_define_method(rb_cConcurrentSet, "size", rb_concurrent_set_size, 0);
+  rb_define_method(rb_cConcurrentSet, "empty?", rb_concurrent_set_empty_p, 0);
+  rb_define_method(rb_cConcurrentSet, "clear", rb_concurrent_set_clear, 0);
+  rb_define_method(rb_cConcurrentSet, "to_a", rb_concurrent_set_to_a, 0);
+  rb_define_method(rb_cConcurrentSet, "each", rb_concurrent_set_each, 0);
+}
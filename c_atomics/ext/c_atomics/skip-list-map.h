@@ -0,0 +1,174 @@
+#include "rust-atomics.h"
+#include <ruby.h>
+
+void rb_concurrent_skip_list_map_mark(void *);
+void rb_concurrent_skip_list_map_free(void *);
+
+const rb_data_type_t concurrent_skip_list_map_data = {
+    .function = {.dfree = rb_concurrent_skip_list_map_free,
+                 .dmark = rb_concurrent_skip_list_map_mark},
+    .flags = RUBY_TYPED_FROZEN_SHAREABLE};
+
+void rb_concurrent_skip_list_map_free(void *ptr) {
+  concurrent_skip_list_map_t *map = ptr;
+  concurrent_skip_list_map_drop(map);
+}
+
+void rb_concurrent_skip_list_map_mark(void *ptr) {
+  concurrent_skip_list_map_t *map = ptr;
+  concurrent_skip_list_map_mark(map, rb_gc_mark);
+}
+
+VALUE rb_concurrent_skip_list_map_alloc(VALUE klass) {
+  concurrent_skip_list_map_t *map;
+  TypedData_Make_Struct0(obj, klass, concurrent_skip_list_map_t,
+                         CONCURRENT_SKIP_LIST_MAP_SIZE,
+                         &concurrent_skip_list_map_data, map);
+  concurrent_skip_list_map_alloc(map);
+  VALUE rb_mCAtomics = rb_const_get(rb_cObject, rb_intern("CAtomics"));
+  VALUE undefined = rb_const_get(rb_mCAtomics, rb_intern("UNDEFINED"));
+  concurrent_skip_list_map_init(map, undefined);
+  VALUE rb_cRactor = rb_const_get(rb_cObject, rb_intern("Ractor"));
+  rb_funcall(rb_cRactor, rb_intern("make_shareable"), 1, obj);
+  return obj;
+}
+
+static VALUE rb_concurrent_skip_list_map_entry(bool found, int64_t key,
+                                               VALUE value) {
+  return found ? rb_assoc_new(LL2NUM(key), value) : Qnil;
+}
+
+VALUE rb_concurrent_skip_list_map_get(VALUE self, VALUE key) {
+  concurrent_skip_list_map_t *map;
+  TypedData_Get_Struct(self, concurrent_skip_list_map_t,
+                       &concurrent_skip_list_map_data, map);
+  return concurrent_skip_list_map_get(map, NUM2LL(key), Qnil);
+}
+
+VALUE rb_concurrent_skip_list_map_set(VALUE self, VALUE key, VALUE value) {
+  concurrent_skip_list_map_t *map;
+  TypedData_Get_Struct(self, concurrent_skip_list_map_t,
+                       &concurrent_skip_list_map_data, map);
+  concurrent_skip_list_map_set(map, NUM2LL(key), value);
+  return Qnil;
+}
+
+VALUE rb_concurrent_skip_list_map_delete(VALUE self, VALUE key) {
+  concurrent_skip_list_map_t *map;
+  TypedData_Get_Struct(self, concurrent_skip_list_map_t,
+                       &concurrent_skip_list_map_data, map);
+  return concurrent_skip_list_map_delete(map, NUM2LL(key), Qnil);
+}
+
+VALUE rb_concurrent_skip_list_map_first(VALUE self) {
+  concurrent_skip_list_map_t *map;
+  TypedData_Get_Struct(self, concurrent_skip_list_map_t,
+                       &concurrent_skip_list_map_data, map);
+  int64_t key = 0;
+  VALUE value = Qnil;
+  bool found = concurrent_skip_list_map_first(map, &key, &value);
+  return rb_concurrent_skip_list_map_entry(found, key, value);
+}
+
+VALUE rb_concurrent_skip_list_map_last(VALUE self) {
+  concurrent_skip_list_map_t *map;
+  TypedData_Get_Struct(self, concurrent_skip_list_map_t,
+                       &concurrent_skip_list_map_data, map);
+  int64_t key = 0;
+  VALUE value = Qnil;
+  bool found = concurrent_skip_list_map_last(map, &key, &value);
+  return rb_concurrent_skip_list_map_entry(found, key, value);
+}
+
+VALUE rb_concurrent_skip_list_map_floor(VALUE self, VALUE target) {
+  concurrent_skip_list_map_t *map;
+  TypedData_Get_Struct(self, concurrent_skip_list_map_t,
+                       &concurrent_skip_list_map_data, map);
+  int64_t key = 0;
+  VALUE value = Qnil;
+  bool found =
+      concurrent_skip_list_map_floor(map, NUM2LL(target), &key, &value);
+  return rb_concurrent_skip_list_map_entry(found, key, value);
+}
+
+VALUE rb_concurrent_skip_list_map_ceiling(VALUE self, VALUE target) {
+  concurrent_skip_list_map_t *map;
+  TypedData_Get_Struct(self, concurrent_skip_list_map_t,
+                       &concurrent_skip_list_map_data, map);
+  int64_t key = 0;
+  VALUE value = Qnil;
+  bool found =
+      concurrent_skip_list_map_ceiling(map, NUM2LL(target), &key, &value);
+  return rb_concurrent_skip_list_map_entry(found, key, value);
+}
+
+// Values are copied into a GC-visible buffer, other Ractors may delete them
+// while we build the result
+VALUE rb_concurrent_skip_list_map_range(VALUE self, VALUE lo, VALUE hi) {
+  concurrent_skip_list_map_t *map;
+  TypedData_Get_Struct(self, concurrent_skip_list_map_t,
+                       &concurrent_skip_list_map_data, map);
+  int64_t lo_key = NUM2LL(lo), hi_key = NUM2LL(hi);
+  size_t capa = 64;
+  while (true) {
+    VALUE keys_tmp, values_tmp;
+    int64_t *keys = rb_alloc_tmp_buffer(&keys_tmp, sizeof(int64_t) * capa);
+    VALUE *values = rb_alloc_tmp_buffer(&values_tmp, sizeof(VALUE) * capa);
+    size_t len = concurrent_skip_list_map_range(map, lo_key, hi_key, keys,
+                                                values, capa);
+    if (len <= capa) {
+      VALUE result = rb_ary_new_capa(len);
+      for (size_t i = 0; i < len; i++) {
+        rb_ary_push(result, rb_assoc_new(LL2NUM(keys[i]), values[i]));
+      }
+      rb_free_tmp_buffer(&values_tmp);
+      rb_free_tmp_buffer(&keys_tmp);
+      return result;
+    }
+    rb_free_tmp_buffer(&values_tmp);
+    rb_free_tmp_buffer(&keys_tmp);
+    capa = len;
+  }
+}
+
+VALUE rb_concurrent_skip_list_map_size(VALUE self) {
+  concurrent_skip_list_map_t *map;
+  TypedData_Get_Struct(self, concurrent_skip_list_map_t,
+                       &concurrent_skip_list_map_data, map);
+  return SIZET2NUM(concurrent_skip_list_map_size(map));
+}
+
+VALUE rb_concurrent_skip_list_map_clear(VALUE self) {
+  concurrent_skip_list_map_t *map;
+  TypedData_Get_Struct(self, concurrent_skip_list_map_t,
+                       &concurrent_skip_list_map_data, map);
+  concurrent_skip_list_map_clear(map);
+  return Qnil;
+}
+
+static void init_skip_list_map(VALUE rb_mCAtomics) {
+  VALUE rb_cConcurrentSkipListMap = rb_define_class_under(
+      rb_mCAtomics, "ConcurrentSkipListMap", rb_cObject);
+  rb_define_alloc_func(rb_cConcurrentSkipListMap,
+                       rb_concurrent_skip_list_map_alloc);
+  rb_define_method(rb_cConcurrentSkipListMap, "get",
+                   rb_concurrent_skip_list_map_get, 1);
+  rb_define_method(rb_cConcurrentSkipListMap, "set",
+                   rb_concurrent_skip_list_map_set, 2);
+  rb_define_method(rb_cConcurrentSkipListMap, "delete",
+                   rb_concurrent_skip_list_map_delete, 1);
+  rb_define_method(rb_cConcurrentSkipListMap, "first",
+                   rb_concurrent_skip_list_map_first, 0);
+  rb_define_method(rb_cConcurrentSkipListMap, "last",
+                   rb_concurrent_skip_list_map_last, 0);
+  rb_define_method(rb_cConcurrentSkipListMap, "floor",
+                   rb_concurrent_skip_list_map_floor, 1);
+  rb_define_method(rb_cConcurrentSkipListMap, "ceiling",
+                   rb_concurrent_skip_list_map_ceiling, 1);
+  rb_define_method(rb_cConcurrentSkipListMap, "range",
+                   rb_concurrent_skip_list_map_range, 2);
+  rb_define_method(rb_cConcurrentSkipListMap, "size",
+                   rb_concurrent_skip_list_map_size, 0);
+  rb_define_method(rb_cConcurrentSkipListMap, "clear",
+                   rb_concurrent_skip_list_map_clear, 0);
+}
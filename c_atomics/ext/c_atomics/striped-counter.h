@@ -0,0 +1,74 @@
+#include "rust-atomics.h"
+#include <ruby.h>
+
+void rb_striped_counter_free(void *);
+
+const rb_data_type_t striped_counter_data = {
+    .function = {.dfree = rb_striped_counter_free},
+    .flags = RUBY_TYPED_FROZEN_SHAREABLE};
+
+void rb_striped_counter_free(void *ptr) {
+  striped_counter_t *counter = ptr;
+  striped_counter_drop(counter);
+}
+
+VALUE rb_striped_counter_alloc(VALUE klass) {
+  striped_counter_t *counter;
+  TypedData_Make_Struct0(obj, klass, striped_counter_t, STRIPED_COUNTER_SIZE,
+                         &striped_counter_data, counter);
+  striped_counter_alloc(counter);
+  VALUE rb_cRactor = rb_const_get(rb_cObject, rb_intern("Ractor"));
+  rb_funcall(rb_cRactor, rb_intern("make_shareable"), 1, obj);
+  return obj;
+}
+
+VALUE rb_striped_counter_initialize(VALUE self, VALUE stripes) {
+  long stripes_count = NUM2LONG(stripes);
+  if (stripes_count < 1) {
+    rb_raise(rb_eArgError, "stripes count must be positive");
+  }
+  striped_counter_t *counter;
+  TypedData_Get_Struct(self, striped_counter_t, &striped_counter_data, counter);
+  striped_counter_init(counter, stripes_count);
+  return Qnil;
+}
+
+VALUE rb_striped_counter_increment(VALUE self) {
+  striped_counter_t *counter;
+  TypedData_Get_Struct(self, striped_counter_t, &striped_counter_data, counter);
+  striped_counter_increment(counter);
+  return Qnil;
+}
+
+VALUE rb_striped_counter_add(VALUE self, VALUE delta) {
+  striped_counter_t *counter;
+  TypedData_Get_Struct(self, striped_counter_t, &striped_counter_data, counter);
+  striped_counter_add(counter, NUM2ULL(delta));
+  return Qnil;
+}
+
+VALUE rb_striped_counter_read(VALUE self) {
+  striped_counter_t *counter;
+  TypedData_Get_Struct(self, striped_counter_t, &striped_counter_data, counter);
+  return ULL2NUM(striped_counter_read(counter));
+}
+
+VALUE rb_striped_counter_get_and_reset(VALUE self) {
+  striped_counter_t *counter;
+  TypedData_Get_Struct(self, striped_counter_t, &striped_counter_data, counter);
+  return ULL2NUM(striped_counter_get_and_reset(counter));
+}
+
+static void init_striped_counter(VALUE rb_mCAtomics) {
+  VALUE rb_cStripedCounter =
+      rb_define_class_under(rb_mCAtomics, "StripedCounter", rb_cObject);
+  rb_define_alloc_func(rb_cStripedCounter, rb_striped_counter_alloc);
+  rb_define_method(rb_cStripedCounter, "initialize",
+                   rb_striped_counter_initialize, 1);
+  rb_define_method(rb_cStripedCounter, "increment",
+                   rb_striped_counter_increment, 0);
+  rb_define_method(rb_cStripedCounter, "add", rb_striped_counter_add, 1);
+  rb_define_method(rb_cStripedCounter, "read", rb_striped_counter_read, 0);
+  rb_define_method(rb_cStripedCounter, "get_and_reset",
+                   rb_striped_counter_get_and_reset, 0);
+}
@@ -0,0 +1,115 @@
+#include "rust-atomics.h"
+#include <ruby.h>
+
+void rb_concurrent_ttl_map_mark(void *);
+void rb_concurrent_ttl_map_free(void *);
+
+const rb_data_type_t concurrent_ttl_map_data = {
+    .function = {.dfree = rb_concurrent_ttl_map_free,
+                 .dmark = rb_concurrent_ttl_map_mark},
+    .flags = RUBY_TYPED_FROZEN_SHAREABLE};
+
+void rb_concurrent_ttl_map_free(void *ptr) {
+  concurrent_ttl_map_t *ttl_map = ptr;
+  concurrent_ttl_map_drop(ttl_map);
+}
+
+void rb_concurrent_ttl_map_mark(void *ptr) {
+  concurrent_ttl_map_t *ttl_map = ptr;
+  concurrent_ttl_map_mark(ttl_map, rb_gc_mark);
+}
+
+VALUE rb_concurrent_ttl_map_alloc(VALUE klass) {
+  concurrent_ttl_map_t *ttl_map;
+  TypedData_Make_Struct0(obj, klass, concurrent_ttl_map_t,
+                         CONCURRENT_TTL_MAP_SIZE, &concurrent_ttl_map_data,
+                         ttl_map);
+  concurrent_ttl_map_init(ttl_map);
+  VALUE rb_cRactor = rb_const_get(rb_cObject, rb_intern("Ractor"));
+  rb_funcall(rb_cRactor, rb_intern("make_shareable"), 1, obj);
+  return obj;
+}
+
+VALUE rb_concurrent_ttl_map_get(VALUE self, VALUE key) {
+  concurrent_ttl_map_t *ttl_map;
+  TypedData_Get_Struct(self, concurrent_ttl_map_t, &concurrent_ttl_map_data,
+                       ttl_map);
+  return concurrent_ttl_map_get(ttl_map, key, Qnil);
+}
+
+// ttl is given in seconds, nil means the entry never expires
+VALUE rb_concurrent_ttl_map_set(int argc, VALUE *argv, VALUE self) {
+  VALUE key, value, ttl;
+  rb_scan_args(argc, argv, "21", &key, &value, &ttl);
+  uint64_t ttl_ms = CONCURRENT_TTL_MAP_NO_TTL;
+  if (!NIL_P(ttl)) {
+    double ms = NUM2DBL(ttl) * 1000;
+    // written as negations to reject NaN too
+    if (!(ms >= 0)) {
+      rb_raise(rb_eArgError, "ttl must be a non-negative number");
+    }
+    // 2 ** 64, anything at or above doesn't fit into uint64_t
+    if (!(ms < 18446744073709551616.0)) {
+      rb_raise(rb_eArgError, "ttl is too large");
+    }
+    ttl_ms = (uint64_t)ms;
+  }
+  concurrent_ttl_map_t *ttl_map;
+  TypedData_Get_Struct(self, concurrent_ttl_map_t, &concurrent_ttl_map_data,
+                       ttl_map);
+  concurrent_ttl_map_set(ttl_map, key, value, ttl_ms);
+  return Qnil;
+}
+
+VALUE rb_concurrent_ttl_map_delete(VALUE self, VALUE key) {
+  concurrent_ttl_map_t *ttl_map;
+  TypedData_Get_Struct(self, concurrent_ttl_map_t, &concurrent_ttl_map_data,
+                       ttl_map);
+  return concurrent_ttl_map_delete(ttl_map, key, Qnil);
+}
+
+VALUE rb_concurrent_ttl_map_key_p(VALUE self, VALUE key) {
+  concurrent_ttl_map_t *ttl_map;
+  TypedData_Get_Struct(self, concurrent_ttl_map_t, &concurrent_ttl_map_data,
+                       ttl_map);
+  return concurrent_ttl_map_contains_key(ttl_map, key) ? Qtrue : Qfalse;
+}
+
+VALUE rb_concurrent_ttl_map_size(VALUE self) {
+  concurrent_ttl_map_t *ttl_map;
+  TypedData_Get_Struct(self, concurrent_ttl_map_t, &concurrent_ttl_map_data,
+                       ttl_map);
+  return SIZET2NUM(concurrent_ttl_map_size(ttl_map));
+}
+
+VALUE rb_concurrent_ttl_map_clear(VALUE self) {
+  concurrent_ttl_map_t *ttl_map;
+  TypedData_Get_Struct(self, concurrent_ttl_map_t, &concurrent_ttl_map_data,
+                       ttl_map);
+  concurrent_ttl_map_clear(ttl_map);
+  return Qnil;
+}
+
+VALUE rb_concurrent_ttl_map_purge_expired(VALUE self) {
+  concurrent_ttl_map_t *ttl_map;
+  TypedData_Get_Struct(self, concurrent_ttl_map_t, &concurrent_ttl_map_data,
+                       ttl_map);
+  return SIZET2NUM(concurrent_ttl_map_purge_expired(ttl_map));
+}
+
+static void init_ttl_map(VALUE rb_mCAtomics) {
+  VALUE rb_cConcurrentTtlMap =
+      rb_define_class_under(rb_mCAtomics, "ConcurrentTtlMap", rb_cObject);
+  rb_define_alloc_func(rb_cConcurrentTtlMap, rb_concurrent_ttl_map_alloc);
+  rb_define_method(rb_cConcurrentTtlMap, "get", rb_concurrent_ttl_map_get, 1);
+  rb_define_method(rb_cConcurrentTtlMap, "set", rb_concurrent_ttl_map_set, -1);
+  rb_define_method(rb_cConcurrentTtlMap, "delete", rb_concurrent_ttl_map_delete,
+                   1);
+  rb_define_method(rb_cConcurrentTtlMap, "key?", rb_concurrent_ttl_map_key_p,
+                   1);
+  rb_define_method(rb_cConcurrentTtlMap, "size", rb_concurrent_ttl_map_size, 0);
+  rb_define_method(rb_cConcurrentTtlMap, "clear", rb_concurrent_ttl_map_clear,
+                   0);
+  rb_define_method(rb_cConcurrentTtlMap, "purge_expired",
+                   rb_concurrent_ttl_map_purge_expired, 0);
+}
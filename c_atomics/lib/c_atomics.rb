@@ -39,6 +39,10 @@ module CAtomics
       fetch_and_modify(key) { |v| v + 1 }
     end
 
+    def add(key, delta)
+      upsert(key) { |v| v.equal?(UNDEFINED) ? delta : v + delta }
+    end
+
     def sum(known_keys)
       known_keys.map { |k| get(k) }.sum
     end
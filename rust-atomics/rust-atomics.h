@@ -6,11 +6,53 @@
 #include <stdint.h>
 #include <stdlib.h>
 
+#if !defined(RUST_ATOMICS_RACE_DETECTOR)
 #define PLAIN_COUNTER_SIZE 8
+#endif
 
-#define ATOMIC_COUNTER_SIZE 8
+#if defined(RUST_ATOMICS_RACE_DETECTOR)
+#define PLAIN_COUNTER_SIZE 24
+#endif
 
-#define CONCURRENT_HASH_MAP_SIZE 40
+#define ATOMIC_COUNTER_OVERFLOW_WRAPPING 0
+
+#define ATOMIC_COUNTER_OVERFLOW_SATURATING 1
+
+#define ATOMIC_COUNTER_OVERFLOW_CHECKED 2
+
+#define ATOMIC_COUNTER_SIZE 16
+
+#define STRIPED_COUNTER_SIZE 16
+
+#define ATOMIC_I64_COUNTER_SIZE 8
+
+#define ATOMIC_F64_SIZE 8
+
+#define ATOMIC_REF_SIZE 8
+
+#define HISTOGRAM_SIZE 48
+
+#define METRICS_REGISTRY_SIZE 40
+
+#define CONCURRENT_HASH_MAP_TX_GET 0
+
+#define CONCURRENT_HASH_MAP_TX_SET 1
+
+#define CONCURRENT_HASH_MAP_TX_DELETE 2
+
+#define CONCURRENT_HASH_MAP_SIZE 80
+
+#define CONCURRENT_LRU_CACHE_SIZE 56
+
+#define CONCURRENT_TTL_MAP_NO_TTL UINT64_MAX
+
+#define CONCURRENT_TTL_MAP_SIZE 48
+
+#define CONCURRENT_SET_SIZE 40
+
+#define CONCURRENT_COUNTER_MAP_SIZE 40
+
+#define CONCURRENT_SKIP_LIST_MAP_SIZE 144
 
 #define FIXED_SIZE_OBJECT_POOL_SIZE 72
 
@@ -22,10 +64,30 @@
 
 typedef struct atomic_counter_t atomic_counter_t;
 
+typedef struct atomic_f64_t atomic_f64_t;
+
+typedef struct atomic_i64_counter_t atomic_i64_counter_t;
+
+typedef struct atomic_ref_t atomic_ref_t;
+
+typedef struct concurrent_counter_map_t concurrent_counter_map_t;
+
 typedef struct concurrent_hash_map_t concurrent_hash_map_t;
 
+typedef struct concurrent_lru_cache_t concurrent_lru_cache_t;
+
+typedef struct concurrent_set_t concurrent_set_t;
+
+typedef struct concurrent_skip_list_map_t concurrent_skip_list_map_t;
+
+typedef struct concurrent_ttl_map_t concurrent_ttl_map_t;
+
 typedef struct fixed_size_object_pool_t fixed_size_object_pool_t;
 
+typedef struct histogram_t histogram_t;
+
+typedef struct metrics_registry_t metrics_registry_t;
+
 typedef struct mpmc_queue_t mpmc_queue_t;
 
 typedef struct plain_counter_t plain_counter_t;
@@ -34,6 +96,21 @@ typedef struct queue_with_mutex_t queue_with_mutex_t;
 
 typedef struct slow_object_t slow_object_t;
 
+typedef struct striped_counter_t striped_counter_t;
+
+typedef struct {
+  uintptr_t size;
+  uintptr_t capacity;
+  uintptr_t shards;
+  uintptr_t bytes;
+} concurrent_hash_map_memory_usage_t;
+
+typedef struct {
+  uint8_t kind;
+  unsigned long key;
+  unsigned long value;
+} concurrent_hash_map_tx_op_t;
+
 typedef struct {
   uintptr_t idx;
   unsigned long rbobj;
@@ -45,21 +122,171 @@ void plain_counter_increment(plain_counter_t *counter);
 
 uint64_t plain_counter_read(const plain_counter_t *counter);
 
-void atomic_counter_init(atomic_counter_t *counter, uint64_t n);
+#if defined(RUST_ATOMICS_RACE_DETECTOR)
+uint64_t plain_counter_races(const plain_counter_t *counter);
+#endif
+
+#if defined(RUST_ATOMICS_RACE_DETECTOR)
+uint64_t plain_counter_last_writer(const plain_counter_t *counter);
+#endif
+
+bool atomic_counter_init(atomic_counter_t *counter, uint64_t n, uint8_t overflow);
 
-void atomic_counter_increment(const atomic_counter_t *counter);
+bool atomic_counter_increment(const atomic_counter_t *counter);
 
 uint64_t atomic_counter_read(const atomic_counter_t *counter);
 
+bool atomic_counter_add(const atomic_counter_t *counter, uint64_t delta, uint64_t *previous);
+
+bool atomic_counter_sub(const atomic_counter_t *counter, uint64_t delta, uint64_t *previous);
+
+uint64_t atomic_counter_swap(const atomic_counter_t *counter, uint64_t n);
+
+bool atomic_counter_compare_exchange(const atomic_counter_t *counter,
+                                     uint64_t expected,
+                                     uint64_t desired,
+                                     uint64_t *previous);
+
+uint64_t atomic_counter_fetch_max(const atomic_counter_t *counter, uint64_t n);
+
+uint64_t atomic_counter_fetch_min(const atomic_counter_t *counter, uint64_t n);
+
+uint64_t atomic_counter_get_and_reset(const atomic_counter_t *counter);
+
+void striped_counter_alloc(striped_counter_t *counter);
+
+void striped_counter_init(striped_counter_t *counter, uintptr_t stripes);
+
+void striped_counter_drop(striped_counter_t *counter);
+
+void striped_counter_increment(const striped_counter_t *counter);
+
+void striped_counter_add(const striped_counter_t *counter, uint64_t delta);
+
+uint64_t striped_counter_read(const striped_counter_t *counter);
+
+uint64_t striped_counter_get_and_reset(const striped_counter_t *counter);
+
+void atomic_i64_counter_init(atomic_i64_counter_t *counter, int64_t n);
+
+void atomic_i64_counter_increment(const atomic_i64_counter_t *counter);
+
+void atomic_i64_counter_decrement(const atomic_i64_counter_t *counter);
+
+int64_t atomic_i64_counter_add(const atomic_i64_counter_t *counter, int64_t delta);
+
+int64_t atomic_i64_counter_read(const atomic_i64_counter_t *counter);
+
+int64_t atomic_i64_counter_swap(const atomic_i64_counter_t *counter, int64_t n);
+
+int64_t atomic_i64_counter_get_and_reset(const atomic_i64_counter_t *counter);
+
+void atomic_f64_init(atomic_f64_t *atomic, double n);
+
+double atomic_f64_add(const atomic_f64_t *atomic, double delta);
+
+double atomic_f64_read(const atomic_f64_t *atomic);
+
+double atomic_f64_swap(const atomic_f64_t *atomic, double n);
+
+double atomic_f64_get_and_reset(const atomic_f64_t *atomic);
+
+void atomic_ref_init(atomic_ref_t *atomic_ref, unsigned long value);
+
+unsigned long atomic_ref_get(const atomic_ref_t *atomic_ref);
+
+void atomic_ref_set(const atomic_ref_t *atomic_ref, unsigned long value);
+
+unsigned long atomic_ref_swap(const atomic_ref_t *atomic_ref, unsigned long value);
+
+bool atomic_ref_compare_and_set(const atomic_ref_t *atomic_ref,
+                                unsigned long expected,
+                                unsigned long value);
+
+void atomic_ref_mark(const atomic_ref_t *atomic_ref, void (*f)(unsigned long));
+
+void histogram_alloc(histogram_t *histogram);
+
+void histogram_init(histogram_t *histogram);
+
+void histogram_drop(histogram_t *histogram);
+
+void histogram_record(const histogram_t *histogram, uint64_t value);
+
+uint64_t histogram_count(const histogram_t *histogram);
+
+uint64_t histogram_sum(const histogram_t *histogram);
+
+uint64_t histogram_min(const histogram_t *histogram);
+
+uint64_t histogram_max(const histogram_t *histogram);
+
+uint64_t histogram_percentile(const histogram_t *histogram, double percentile);
+
+void histogram_merge(const histogram_t *histogram, const histogram_t *other);
+
+void histogram_reset(const histogram_t *histogram);
+
+void metrics_registry_init(metrics_registry_t *registry);
+
+void metrics_registry_drop(metrics_registry_t *registry);
+
+bool metrics_registry_register_counter(const metrics_registry_t *registry,
+                                       const uint8_t *name,
+                                       uintptr_t name_len,
+                                       const uint8_t *help,
+                                       uintptr_t help_len);
+
+bool metrics_registry_register_gauge(const metrics_registry_t *registry,
+                                     const uint8_t *name,
+                                     uintptr_t name_len,
+                                     const uint8_t *help,
+                                     uintptr_t help_len);
+
+bool metrics_registry_register_histogram(const metrics_registry_t *registry,
+                                         const uint8_t *name,
+                                         uintptr_t name_len,
+                                         const uint8_t *help,
+                                         uintptr_t help_len);
+
+bool metrics_registry_counter_add(const metrics_registry_t *registry,
+                                  const uint8_t *name,
+                                  uintptr_t name_len,
+                                  uint64_t delta);
+
+bool metrics_registry_gauge_set(const metrics_registry_t *registry,
+                                const uint8_t *name,
+                                uintptr_t name_len,
+                                double value);
+
+bool metrics_registry_gauge_add(const metrics_registry_t *registry,
+                                const uint8_t *name,
+                                uintptr_t name_len,
+                                double delta);
+
+bool metrics_registry_histogram_record(const metrics_registry_t *registry,
+                                       const uint8_t *name,
+                                       uintptr_t name_len,
+                                       uint64_t value);
+
+uintptr_t metrics_registry_render(const metrics_registry_t *registry, uint8_t *buf, uintptr_t cap);
+
 extern unsigned long rb_hash(unsigned long obj);
 
 extern int rb_eql(unsigned long lhs, unsigned long rhs);
 
-void concurrent_hash_map_init(concurrent_hash_map_t *hashmap);
+void concurrent_hash_map_alloc(concurrent_hash_map_t *hashmap);
+
+void concurrent_hash_map_init(concurrent_hash_map_t *hashmap,
+                              uintptr_t capacity,
+                              uintptr_t shards_count);
 
 void concurrent_hash_map_drop(concurrent_hash_map_t *hashmap);
 
-void concurrent_hash_map_clear(const concurrent_hash_map_t *hashmap);
+bool concurrent_hash_map_clear(const concurrent_hash_map_t *hashmap,
+                               unsigned long *removed,
+                               uintptr_t cap,
+                               uintptr_t *len);
 
 unsigned long concurrent_hash_map_get(const concurrent_hash_map_t *hashmap,
                                       unsigned long key,
@@ -69,11 +296,270 @@ void concurrent_hash_map_set(const concurrent_hash_map_t *hashmap,
                              unsigned long key,
                              unsigned long value);
 
+unsigned long concurrent_hash_map_swap(const concurrent_hash_map_t *hashmap,
+                                     unsigned long key,
+                                     unsigned long value,
+                                     unsigned long fallback);
+
+unsigned long concurrent_hash_map_delete(const concurrent_hash_map_t *hashmap,
+                                         unsigned long key,
+                                         unsigned long fallback);
+
+bool concurrent_hash_map_contains_key(const concurrent_hash_map_t *hashmap, unsigned long key);
+
+uintptr_t concurrent_hash_map_size(const concurrent_hash_map_t *hashmap);
+
+bool concurrent_hash_map_is_empty(const concurrent_hash_map_t *hashmap);
+
+bool concurrent_hash_map_replace_if(const concurrent_hash_map_t *hashmap,
+                                    unsigned long key,
+                                    unsigned long expected,
+                                    unsigned long new_value);
+
+bool concurrent_hash_map_delete_if(const concurrent_hash_map_t *hashmap,
+                                   unsigned long key,
+                                   unsigned long expected);
+
+void concurrent_hash_map_transaction(const concurrent_hash_map_t *hashmap,
+                                     const concurrent_hash_map_tx_op_t *ops,
+                                     uintptr_t len,
+                                     unsigned long *pre_images,
+                                     unsigned long fallback);
+
+void concurrent_hash_map_set_many(const concurrent_hash_map_t *hashmap,
+                                  const unsigned long *keys,
+                                  const unsigned long *values,
+                                  uintptr_t len,
+                                  unsigned long *old_values,
+                                  unsigned long fallback);
+
+void concurrent_hash_map_get_many(const concurrent_hash_map_t *hashmap,
+                                  const unsigned long *keys,
+                                  uintptr_t len,
+                                  unsigned long *out,
+                                  unsigned long fallback);
+
+uintptr_t concurrent_hash_map_delete_many(const concurrent_hash_map_t *hashmap,
+                                          const unsigned long *keys,
+                                          uintptr_t len,
+                                          unsigned long *old_values,
+                                          unsigned long fallback);
+
+uintptr_t concurrent_hash_map_merge(const concurrent_hash_map_t *hashmap,
+                                    const concurrent_hash_map_t *other,
+                                    unsigned long *merged,
+                                    uintptr_t cap,
+                                    unsigned long fallback);
+
+void concurrent_hash_map_subscribe(const concurrent_hash_map_t *hashmap,
+                                   unsigned long queue_obj,
+                                   const mpmc_queue_t *queue);
+
+bool concurrent_hash_map_unsubscribe(const concurrent_hash_map_t *hashmap, unsigned long queue_obj);
+
+bool concurrent_hash_map_has_subscribers(const concurrent_hash_map_t *hashmap);
+
+uintptr_t concurrent_hash_map_publish(const concurrent_hash_map_t *hashmap, unsigned long event);
+
+void concurrent_hash_map_shrink_to_fit(const concurrent_hash_map_t *hashmap);
+
+concurrent_hash_map_memory_usage_t concurrent_hash_map_memory_usage(const concurrent_hash_map_t *hashmap);
+
 void concurrent_hash_map_mark(const concurrent_hash_map_t *hashmap, void (*f)(unsigned long));
 
-void concurrent_hash_map_fetch_and_modify(const concurrent_hash_map_t *hashmap,
+unsigned long concurrent_hash_map_put_if_absent(const concurrent_hash_map_t *hashmap,
+                                                unsigned long key,
+                                                unsigned long value,
+                                                unsigned long *old_value,
+                                                unsigned long fallback);
+
+unsigned long concurrent_hash_map_compute_if_absent(const concurrent_hash_map_t *hashmap,
+                                                    unsigned long key,
+                                                    unsigned long (*f)(unsigned long, int*),
+                                                    unsigned long *old_value,
+                                                    unsigned long fallback,
+                                                    int *state);
+
+uintptr_t concurrent_hash_map_snapshot(const concurrent_hash_map_t *hashmap,
+                                       unsigned long *pairs,
+                                       uintptr_t cap);
+
+uintptr_t concurrent_hash_map_keys(const concurrent_hash_map_t *hashmap,
+                                   unsigned long *keys,
+                                   uintptr_t cap);
+
+uintptr_t concurrent_hash_map_values(const concurrent_hash_map_t *hashmap,
+                                     unsigned long *values,
+                                     uintptr_t cap);
+
+unsigned long concurrent_hash_map_fetch_and_modify(const concurrent_hash_map_t *hashmap,
+                                                   unsigned long key,
+                                                   unsigned long (*f)(unsigned long, int*),
+                                                   unsigned long *old_value,
+                                                   unsigned long fallback,
+                                                   int *state);
+
+unsigned long concurrent_hash_map_upsert(const concurrent_hash_map_t *hashmap,
+                                         unsigned long key,
+                                         unsigned long absent,
+                                         unsigned long (*f)(unsigned long, int*),
+                                         unsigned long *old_value,
+                                         int *state);
+
+unsigned long concurrent_hash_map_compute(const concurrent_hash_map_t *hashmap,
+                                          unsigned long key,
+                                          unsigned long absent,
+                                          unsigned long (*f)(unsigned long),
+                                          unsigned long *old_value);
+
+void concurrent_lru_cache_alloc(concurrent_lru_cache_t *cache);
+
+void concurrent_lru_cache_init(concurrent_lru_cache_t *cache,
+                               uintptr_t capacity,
+                               uintptr_t shards_count);
+
+void concurrent_lru_cache_drop(concurrent_lru_cache_t *cache);
+
+void concurrent_lru_cache_mark(const concurrent_lru_cache_t *cache, void (*f)(unsigned long));
+
+unsigned long concurrent_lru_cache_get(const concurrent_lru_cache_t *cache,
+                                       unsigned long key,
+                                       unsigned long fallback);
+
+void concurrent_lru_cache_set(const concurrent_lru_cache_t *cache,
+                              unsigned long key,
+                              unsigned long value);
+
+unsigned long concurrent_lru_cache_delete(const concurrent_lru_cache_t *cache,
                                           unsigned long key,
-                                          unsigned long (*f)(unsigned long));
+                                          unsigned long fallback);
+
+uintptr_t concurrent_lru_cache_size(const concurrent_lru_cache_t *cache);
+
+void concurrent_lru_cache_clear(const concurrent_lru_cache_t *cache);
+
+uint64_t concurrent_lru_cache_hits(const concurrent_lru_cache_t *cache);
+
+uint64_t concurrent_lru_cache_misses(const concurrent_lru_cache_t *cache);
+
+void concurrent_ttl_map_init(concurrent_ttl_map_t *ttl_map);
+
+void concurrent_ttl_map_drop(concurrent_ttl_map_t *ttl_map);
+
+void concurrent_ttl_map_mark(const concurrent_ttl_map_t *ttl_map, void (*f)(unsigned long));
+
+unsigned long concurrent_ttl_map_get(const concurrent_ttl_map_t *ttl_map,
+                                     unsigned long key,
+                                     unsigned long fallback);
+
+void concurrent_ttl_map_set(const concurrent_ttl_map_t *ttl_map,
+                            unsigned long key,
+                            unsigned long value,
+                            uint64_t ttl_ms);
+
+unsigned long concurrent_ttl_map_delete(const concurrent_ttl_map_t *ttl_map,
+                                        unsigned long key,
+                                        unsigned long fallback);
+
+bool concurrent_ttl_map_contains_key(const concurrent_ttl_map_t *ttl_map, unsigned long key);
+
+uintptr_t concurrent_ttl_map_size(const concurrent_ttl_map_t *ttl_map);
+
+void concurrent_ttl_map_clear(const concurrent_ttl_map_t *ttl_map);
+
+uintptr_t concurrent_ttl_map_purge_expired(const concurrent_ttl_map_t *ttl_map);
+
+void concurrent_set_init(concurrent_set_t *set);
+
+void concurrent_set_drop(concurrent_set_t *set);
+
+void concurrent_set_mark(const concurrent_set_t *set, void (*f)(unsigned long));
+
+bool concurrent_set_add(const concurrent_set_t *set, unsigned long key);
+
+bool concurrent_set_delete(const concurrent_set_t *set, unsigned long key);
+
+bool concurrent_set_contains(const concurrent_set_t *set, unsigned long key);
+
+uintptr_t concurrent_set_size(const concurrent_set_t *set);
+
+bool concurrent_set_is_empty(const concurrent_set_t *set);
+
+void concurrent_set_clear(const concurrent_set_t *set);
+
+uintptr_t concurrent_set_snapshot(const concurrent_set_t *set, unsigned long *keys, uintptr_t cap);
+
+void concurrent_counter_map_init(concurrent_counter_map_t *counter_map);
+
+void concurrent_counter_map_drop(concurrent_counter_map_t *counter_map);
+
+void concurrent_counter_map_mark(const concurrent_counter_map_t *counter_map,
+                                 void (*f)(unsigned long));
+
+uint64_t concurrent_counter_map_increment(const concurrent_counter_map_t *counter_map,
+                                          unsigned long key,
+                                          uint64_t delta);
+
+uint64_t concurrent_counter_map_get(const concurrent_counter_map_t *counter_map,
+                                    unsigned long key);
+
+uintptr_t concurrent_counter_map_top_n(const concurrent_counter_map_t *counter_map,
+                                       uintptr_t n,
+                                       unsigned long *keys,
+                                       uint64_t *counts);
+
+uintptr_t concurrent_counter_map_size(const concurrent_counter_map_t *counter_map);
+
+void concurrent_counter_map_reset(const concurrent_counter_map_t *counter_map);
+
+void concurrent_skip_list_map_alloc(concurrent_skip_list_map_t *map);
+
+void concurrent_skip_list_map_init(concurrent_skip_list_map_t *map, unsigned long absent);
+
+void concurrent_skip_list_map_drop(concurrent_skip_list_map_t *map);
+
+void concurrent_skip_list_map_mark(const concurrent_skip_list_map_t *map, void (*f)(unsigned long));
+
+unsigned long concurrent_skip_list_map_get(const concurrent_skip_list_map_t *map,
+                                           int64_t key,
+                                           unsigned long fallback);
+
+void concurrent_skip_list_map_set(const concurrent_skip_list_map_t *map,
+                                  int64_t key,
+                                  unsigned long value);
+
+unsigned long concurrent_skip_list_map_delete(const concurrent_skip_list_map_t *map,
+                                              int64_t key,
+                                              unsigned long fallback);
+
+bool concurrent_skip_list_map_first(const concurrent_skip_list_map_t *map,
+                                    int64_t *key,
+                                    unsigned long *value);
+
+bool concurrent_skip_list_map_last(const concurrent_skip_list_map_t *map,
+                                   int64_t *key,
+                                   unsigned long *value);
+
+bool concurrent_skip_list_map_floor(const concurrent_skip_list_map_t *map,
+                                    int64_t target,
+                                    int64_t *key,
+                                    unsigned long *value);
+
+bool concurrent_skip_list_map_ceiling(const concurrent_skip_list_map_t *map,
+                                      int64_t target,
+                                      int64_t *key,
+                                      unsigned long *value);
+
+uintptr_t concurrent_skip_list_map_range(const concurrent_skip_list_map_t *map,
+                                         int64_t lo,
+                                         int64_t hi,
+                                         int64_t *keys,
+                                         unsigned long *values,
+                                         uintptr_t cap);
+
+uintptr_t concurrent_skip_list_map_size(const concurrent_skip_list_map_t *map);
+
+void concurrent_skip_list_map_clear(const concurrent_skip_list_map_t *map);
 
 void fixed_size_object_pool_alloc(fixed_size_object_pool_t *pool);
 
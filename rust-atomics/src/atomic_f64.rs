@@ -0,0 +1,83 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Debug)]
+pub struct AtomicF64 {
+    bits: AtomicU64,
+}
+
+impl AtomicF64 {
+    pub fn new(n: f64) -> Self {
+        Self {
+            bits: AtomicU64::new(n.to_bits()),
+        }
+    }
+
+    pub fn add(&self, delta: f64) -> f64 {
+        let mut current = self.bits.load(Ordering::Relaxed);
+        loop {
+            let new = (f64::from_bits(current) + delta).to_bits();
+            match self
+                .bits
+                .compare_exchange_weak(current, new, Ordering::AcqRel, Ordering::Relaxed)
+            {
+                Ok(prev) => return f64::from_bits(prev),
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
+    pub fn read(&self) -> f64 {
+        f64::from_bits(self.bits.load(Ordering::Relaxed))
+    }
+
+    pub fn swap(&self, n: f64) -> f64 {
+        f64::from_bits(self.bits.swap(n.to_bits(), Ordering::AcqRel))
+    }
+
+    pub fn get_and_reset(&self) -> f64 {
+        self.swap(0.0)
+    }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn atomic_f64_init(atomic: *mut AtomicF64, n: f64) {
+    unsafe { atomic.write(AtomicF64::new(n)) }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn atomic_f64_add(atomic: *const AtomicF64, delta: f64) -> f64 {
+    let atomic = unsafe { atomic.as_ref().unwrap() };
+    atomic.add(delta)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn atomic_f64_read(atomic: *const AtomicF64) -> f64 {
+    let atomic = unsafe { atomic.as_ref().unwrap() };
+    atomic.read()
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn atomic_f64_swap(atomic: *const AtomicF64, n: f64) -> f64 {
+    let atomic = unsafe { atomic.as_ref().unwrap() };
+    atomic.swap(n)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn atomic_f64_get_and_reset(atomic: *const AtomicF64) -> f64 {
+    let atomic = unsafe { atomic.as_ref().unwrap() };
+    atomic.get_and_reset()
+}
+
+pub const ATOMIC_F64_SIZE: usize = 8;
+
+#[test]
+fn test_atomic_f64() {
+    assert_eq!(ATOMIC_F64_SIZE, std::mem::size_of::<AtomicF64>());
+    assert!(crate::is_sync_and_send::<AtomicF64>());
+
+    let atomic = AtomicF64::new(1.5);
+    assert_eq!(atomic.add(0.25), 1.5);
+    assert_eq!(atomic.add(-2.0), 1.75);
+    assert_eq!(atomic.get_and_reset(), -0.25);
+    assert_eq!(atomic.read(), 0.0);
+}
@@ -0,0 +1,93 @@
+use std::sync::atomic::{AtomicI64, Ordering};
+
+#[derive(Debug)]
+pub struct AtomicI64Counter {
+    value: AtomicI64,
+}
+
+impl AtomicI64Counter {
+    pub fn new(n: i64) -> Self {
+        Self {
+            value: AtomicI64::new(n),
+        }
+    }
+
+    pub fn inc(&self) {
+        self.value.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn dec(&self) {
+        self.value.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn add(&self, delta: i64) -> i64 {
+        self.value.fetch_add(delta, Ordering::Relaxed)
+    }
+
+    pub fn read(&self) -> i64 {
+        self.value.load(Ordering::Relaxed)
+    }
+
+    pub fn swap(&self, n: i64) -> i64 {
+        self.value.swap(n, Ordering::AcqRel)
+    }
+
+    pub fn get_and_reset(&self) -> i64 {
+        self.swap(0)
+    }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn atomic_i64_counter_init(counter: *mut AtomicI64Counter, n: i64) {
+    unsafe { counter.write(AtomicI64Counter::new(n)) }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn atomic_i64_counter_increment(counter: *const AtomicI64Counter) {
+    let counter = unsafe { counter.as_ref().unwrap() };
+    counter.inc();
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn atomic_i64_counter_decrement(counter: *const AtomicI64Counter) {
+    let counter = unsafe { counter.as_ref().unwrap() };
+    counter.dec();
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn atomic_i64_counter_add(
+    counter: *const AtomicI64Counter,
+    delta: i64,
+) -> i64 {
+    let counter = unsafe { counter.as_ref().unwrap() };
+    counter.add(delta)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn atomic_i64_counter_read(counter: *const AtomicI64Counter) -> i64 {
+    let counter = unsafe { counter.as_ref().unwrap() };
+    counter.read()
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn atomic_i64_counter_swap(counter: *const AtomicI64Counter, n: i64) -> i64 {
+    let counter = unsafe { counter.as_ref().unwrap() };
+    counter.swap(n)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn atomic_i64_counter_get_and_reset(counter: *const AtomicI64Counter) -> i64 {
+    let counter = unsafe { counter.as_ref().unwrap() };
+    counter.get_and_reset()
+}
+
+pub const ATOMIC_I64_COUNTER_SIZE: usize = 8;
+
+#[test]
+fn test_atomic_i64_counter() {
+    assert_eq!(
+        ATOMIC_I64_COUNTER_SIZE,
+        std::mem::size_of::<AtomicI64Counter>()
+    );
+    assert!(crate::is_sync_and_send::<AtomicI64Counter>());
+}
@@ -0,0 +1,90 @@
+use std::{
+    ffi::c_ulong,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+#[derive(Debug)]
+pub struct AtomicRef {
+    value: AtomicU64,
+}
+
+impl AtomicRef {
+    fn new(value: c_ulong) -> Self {
+        Self {
+            value: AtomicU64::new(value),
+        }
+    }
+
+    fn get(&self) -> c_ulong {
+        self.value.load(Ordering::Acquire)
+    }
+
+    fn set(&self, value: c_ulong) {
+        self.value.store(value, Ordering::Release);
+    }
+
+    fn swap(&self, value: c_ulong) -> c_ulong {
+        self.value.swap(value, Ordering::AcqRel)
+    }
+
+    fn compare_and_set(&self, expected: c_ulong, value: c_ulong) -> bool {
+        self.value
+            .compare_exchange(expected, value, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+    }
+
+    fn mark(&self, f: extern "C" fn(c_ulong)) {
+        f(self.get());
+    }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn atomic_ref_init(atomic_ref: *mut AtomicRef, value: c_ulong) {
+    unsafe { atomic_ref.write(AtomicRef::new(value)) }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn atomic_ref_get(atomic_ref: *const AtomicRef) -> c_ulong {
+    let atomic_ref = unsafe { atomic_ref.as_ref().unwrap() };
+    atomic_ref.get()
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn atomic_ref_set(atomic_ref: *const AtomicRef, value: c_ulong) {
+    let atomic_ref = unsafe { atomic_ref.as_ref().unwrap() };
+    atomic_ref.set(value);
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn atomic_ref_swap(atomic_ref: *const AtomicRef, value: c_ulong) -> c_ulong {
+    let atomic_ref = unsafe { atomic_ref.as_ref().unwrap() };
+    atomic_ref.swap(value)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn atomic_ref_compare_and_set(
+    atomic_ref: *const AtomicRef,
+    expected: c_ulong,
+    value: c_ulong,
+) -> bool {
+    let atomic_ref = unsafe { atomic_ref.as_ref().unwrap() };
+    atomic_ref.compare_and_set(expected, value)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn atomic_ref_mark(atomic_ref: *const AtomicRef, f: extern "C" fn(c_ulong)) {
+    let atomic_ref = unsafe { atomic_ref.as_ref().unwrap() };
+    atomic_ref.mark(f);
+}
+
+pub const ATOMIC_REF_SIZE: usize = 8;
+
+#[test]
+fn test_atomic_ref() {
+    assert_eq!(
+        ATOMIC_REF_SIZE,
+        std::mem::size_of::<AtomicRef>(),
+        "size mismatch"
+    );
+    assert!(crate::is_sync_and_send::<AtomicRef>());
+}
@@ -1,35 +1,120 @@
 use std::sync::atomic::{AtomicU64, Ordering};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CounterOverflow {
+    Wrapping,
+    Saturating,
+    Checked,
+}
+
+pub const ATOMIC_COUNTER_OVERFLOW_WRAPPING: u8 = 0;
+pub const ATOMIC_COUNTER_OVERFLOW_SATURATING: u8 = 1;
+pub const ATOMIC_COUNTER_OVERFLOW_CHECKED: u8 = 2;
+
+impl TryFrom<u8> for CounterOverflow {
+    type Error = u8;
+
+    fn try_from(mode: u8) -> Result<Self, Self::Error> {
+        match mode {
+            ATOMIC_COUNTER_OVERFLOW_WRAPPING => Ok(Self::Wrapping),
+            ATOMIC_COUNTER_OVERFLOW_SATURATING => Ok(Self::Saturating),
+            ATOMIC_COUNTER_OVERFLOW_CHECKED => Ok(Self::Checked),
+            _ => Err(mode),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct CounterOverflowError {
+    pub previous: u64,
+}
+
 #[derive(Debug)]
 pub struct AtomicCounter {
     value: AtomicU64,
+    overflow: CounterOverflow,
 }
 
 impl AtomicCounter {
-    pub fn new(n: u64) -> Self {
+    pub fn new(n: u64, overflow: CounterOverflow) -> Self {
         Self {
             value: AtomicU64::new(n),
+            overflow,
         }
     }
 
-    pub fn inc(&self) {
-        self.value.fetch_add(1, Ordering::Relaxed);
+    pub fn inc(&self) -> Result<u64, CounterOverflowError> {
+        self.add(1)
     }
 
     pub fn read(&self) -> u64 {
         self.value.load(Ordering::Relaxed)
     }
+
+    pub fn add(&self, delta: u64) -> Result<u64, CounterOverflowError> {
+        match self.overflow {
+            CounterOverflow::Wrapping => Ok(self.value.fetch_add(delta, Ordering::Relaxed)),
+            CounterOverflow::Saturating => self.update(|n| Some(n.saturating_add(delta))),
+            CounterOverflow::Checked => self.update(|n| n.checked_add(delta)),
+        }
+    }
+
+    pub fn sub(&self, delta: u64) -> Result<u64, CounterOverflowError> {
+        match self.overflow {
+            CounterOverflow::Wrapping => Ok(self.value.fetch_sub(delta, Ordering::Relaxed)),
+            CounterOverflow::Saturating => self.update(|n| Some(n.saturating_sub(delta))),
+            CounterOverflow::Checked => self.update(|n| n.checked_sub(delta)),
+        }
+    }
+
+    fn update<F>(&self, f: F) -> Result<u64, CounterOverflowError>
+    where
+        F: FnMut(u64) -> Option<u64>,
+    {
+        self.value
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, f)
+            .map_err(|previous| CounterOverflowError { previous })
+    }
+
+    pub fn swap(&self, n: u64) -> u64 {
+        self.value.swap(n, Ordering::AcqRel)
+    }
+
+    pub fn compare_exchange(&self, expected: u64, desired: u64) -> Result<u64, u64> {
+        self.value
+            .compare_exchange(expected, desired, Ordering::AcqRel, Ordering::Acquire)
+    }
+
+    pub fn fetch_max(&self, n: u64) -> u64 {
+        self.value.fetch_max(n, Ordering::Relaxed)
+    }
+
+    pub fn fetch_min(&self, n: u64) -> u64 {
+        self.value.fetch_min(n, Ordering::Relaxed)
+    }
+
+    pub fn get_and_reset(&self) -> u64 {
+        self.swap(0)
+    }
 }
 
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn atomic_counter_init(counter: *mut AtomicCounter, n: u64) {
-    unsafe { counter.write(AtomicCounter::new(n)) }
+pub unsafe extern "C" fn atomic_counter_init(
+    counter: *mut AtomicCounter,
+    n: u64,
+    overflow: u8,
+) -> bool {
+    let Ok(overflow) = CounterOverflow::try_from(overflow) else {
+        return false;
+    };
+    unsafe { counter.write(AtomicCounter::new(n, overflow)) };
+    true
 }
 
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn atomic_counter_increment(counter: *const AtomicCounter) {
+pub unsafe extern "C" fn atomic_counter_increment(counter: *const AtomicCounter) -> bool {
     let counter = unsafe { counter.as_ref().unwrap() };
-    counter.inc();
+    counter.inc().is_ok()
 }
 
 #[unsafe(no_mangle)]
@@ -38,10 +123,136 @@ pub unsafe extern "C" fn atomic_counter_read(counter: *const AtomicCounter) -> u
     counter.read()
 }
 
-pub const ATOMIC_COUNTER_SIZE: usize = 8;
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn atomic_counter_add(
+    counter: *const AtomicCounter,
+    delta: u64,
+    previous: *mut u64,
+) -> bool {
+    let counter = unsafe { counter.as_ref().unwrap() };
+    let result = counter.add(delta);
+    unsafe { write_previous(result, previous) }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn atomic_counter_sub(
+    counter: *const AtomicCounter,
+    delta: u64,
+    previous: *mut u64,
+) -> bool {
+    let counter = unsafe { counter.as_ref().unwrap() };
+    let result = counter.sub(delta);
+    unsafe { write_previous(result, previous) }
+}
+
+unsafe fn write_previous(result: Result<u64, CounterOverflowError>, out: *mut u64) -> bool {
+    let (ok, value) = match result {
+        Ok(value) => (true, value),
+        Err(CounterOverflowError { previous }) => (false, previous),
+    };
+    if let Some(out) = unsafe { out.as_mut() } {
+        *out = value;
+    }
+    ok
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn atomic_counter_swap(counter: *const AtomicCounter, n: u64) -> u64 {
+    let counter = unsafe { counter.as_ref().unwrap() };
+    counter.swap(n)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn atomic_counter_compare_exchange(
+    counter: *const AtomicCounter,
+    expected: u64,
+    desired: u64,
+    previous: *mut u64,
+) -> bool {
+    let counter = unsafe { counter.as_ref().unwrap() };
+    let (swapped, value) = match counter.compare_exchange(expected, desired) {
+        Ok(value) => (true, value),
+        Err(value) => (false, value),
+    };
+    if let Some(previous) = unsafe { previous.as_mut() } {
+        *previous = value;
+    }
+    swapped
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn atomic_counter_fetch_max(counter: *const AtomicCounter, n: u64) -> u64 {
+    let counter = unsafe { counter.as_ref().unwrap() };
+    counter.fetch_max(n)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn atomic_counter_fetch_min(counter: *const AtomicCounter, n: u64) -> u64 {
+    let counter = unsafe { counter.as_ref().unwrap() };
+    counter.fetch_min(n)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn atomic_counter_get_and_reset(counter: *const AtomicCounter) -> u64 {
+    let counter = unsafe { counter.as_ref().unwrap() };
+    counter.get_and_reset()
+}
+
+pub const ATOMIC_COUNTER_SIZE: usize = 16;
 
 #[test]
 fn test_atomic_counter() {
     assert_eq!(ATOMIC_COUNTER_SIZE, std::mem::size_of::<AtomicCounter>());
     assert!(crate::is_sync_and_send::<AtomicCounter>());
 }
+
+#[test]
+fn test_atomic_counter_ops() {
+    let counter = AtomicCounter::new(10, CounterOverflow::Wrapping);
+    assert_eq!(counter.add(5), Ok(10));
+    assert_eq!(counter.sub(3), Ok(15));
+    assert_eq!(counter.swap(100), 12);
+    assert_eq!(counter.compare_exchange(1, 2), Err(100));
+    assert_eq!(counter.compare_exchange(100, 50), Ok(100));
+    assert_eq!(counter.fetch_max(70), 50);
+    assert_eq!(counter.fetch_min(20), 70);
+    assert_eq!(counter.get_and_reset(), 20);
+    assert_eq!(counter.read(), 0);
+}
+
+#[test]
+fn test_atomic_counter_overflow() {
+    let counter = AtomicCounter::new(u64::MAX - 1, CounterOverflow::Wrapping);
+    assert_eq!(counter.add(2), Ok(u64::MAX - 1));
+    assert_eq!(counter.read(), 0);
+
+    let counter = AtomicCounter::new(u64::MAX - 1, CounterOverflow::Saturating);
+    assert_eq!(counter.add(2), Ok(u64::MAX - 1));
+    assert_eq!(counter.read(), u64::MAX);
+    assert_eq!(counter.sub(u64::MAX), Ok(u64::MAX));
+    assert_eq!(counter.sub(1), Ok(0));
+    assert_eq!(counter.read(), 0);
+
+    let counter = AtomicCounter::new(u64::MAX - 1, CounterOverflow::Checked);
+    assert_eq!(counter.inc(), Ok(u64::MAX - 1));
+    assert_eq!(
+        counter.inc(),
+        Err(CounterOverflowError { previous: u64::MAX })
+    );
+    assert_eq!(counter.read(), u64::MAX);
+}
+
+#[test]
+fn test_atomic_counter_overflow_mode() {
+    assert_eq!(
+        CounterOverflow::try_from(ATOMIC_COUNTER_OVERFLOW_CHECKED),
+        Ok(CounterOverflow::Checked)
+    );
+    assert_eq!(CounterOverflow::try_from(42), Err(42));
+
+    let mut counter = std::mem::MaybeUninit::<AtomicCounter>::uninit();
+    assert!(!unsafe { atomic_counter_init(counter.as_mut_ptr(), 0, 42) });
+    assert!(unsafe {
+        atomic_counter_init(counter.as_mut_ptr(), 0, ATOMIC_COUNTER_OVERFLOW_WRAPPING)
+    });
+}
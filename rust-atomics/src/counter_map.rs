@@ -0,0 +1,195 @@
+use crate::hashmap::{HashedKey, mark_shards};
+use std::{
+    ffi::c_ulong,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+pub struct ConcurrentCounterMap {
+    map: dashmap::DashMap<HashedKey, AtomicU64>,
+}
+
+impl ConcurrentCounterMap {
+    fn new() -> Self {
+        Self {
+            map: dashmap::DashMap::new(),
+        }
+    }
+
+    fn increment(&self, key: c_ulong, delta: u64) -> u64 {
+        let key = HashedKey::new(key);
+        // existing keys only need a shared lock on their shard
+        if let Some(counter) = self.map.get(&key) {
+            return counter
+                .fetch_add(delta, Ordering::Relaxed)
+                .wrapping_add(delta);
+        }
+        self.map
+            .entry(key)
+            .or_insert_with(|| AtomicU64::new(0))
+            .fetch_add(delta, Ordering::Relaxed)
+            .wrapping_add(delta)
+    }
+
+    fn get(&self, key: c_ulong) -> u64 {
+        self.map
+            .get(&HashedKey::new(key))
+            .map_or(0, |counter| counter.load(Ordering::Relaxed))
+    }
+
+    fn top_n(&self, n: usize) -> Vec<(c_ulong, u64)> {
+        let mut counts = self
+            .map
+            .iter()
+            .map(|pair| (pair.key().key.0, pair.value().load(Ordering::Relaxed)))
+            .collect::<Vec<_>>();
+        counts.sort_unstable_by(|(_, lhs), (_, rhs)| rhs.cmp(lhs));
+        counts.truncate(n);
+        counts
+    }
+
+    fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    fn reset(&self) {
+        self.map.clear()
+    }
+
+    fn mark(&self, f: extern "C" fn(c_ulong)) {
+        mark_shards(&self.map, |key, _| f(key.key.0));
+    }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_counter_map_init(counter_map: *mut ConcurrentCounterMap) {
+    unsafe { counter_map.write(ConcurrentCounterMap::new()) }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_counter_map_drop(counter_map: *mut ConcurrentCounterMap) {
+    unsafe { std::ptr::drop_in_place(counter_map) };
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_counter_map_mark(
+    counter_map: *const ConcurrentCounterMap,
+    f: extern "C" fn(c_ulong),
+) {
+    let counter_map = unsafe { counter_map.as_ref().unwrap() };
+    counter_map.mark(f);
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_counter_map_increment(
+    counter_map: *const ConcurrentCounterMap,
+    key: c_ulong,
+    delta: u64,
+) -> u64 {
+    let counter_map = unsafe { counter_map.as_ref().unwrap() };
+    counter_map.increment(key, delta)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_counter_map_get(
+    counter_map: *const ConcurrentCounterMap,
+    key: c_ulong,
+) -> u64 {
+    let counter_map = unsafe { counter_map.as_ref().unwrap() };
+    counter_map.get(key)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_counter_map_top_n(
+    counter_map: *const ConcurrentCounterMap,
+    n: usize,
+    keys: *mut c_ulong,
+    counts: *mut u64,
+) -> usize {
+    let counter_map = unsafe { counter_map.as_ref().unwrap() };
+    let top = counter_map.top_n(n);
+    for (idx, (key, count)) in top.iter().enumerate() {
+        unsafe {
+            keys.add(idx).write(*key);
+            counts.add(idx).write(*count);
+        }
+    }
+    top.len()
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_counter_map_size(
+    counter_map: *const ConcurrentCounterMap,
+) -> usize {
+    let counter_map = unsafe { counter_map.as_ref().unwrap() };
+    counter_map.len()
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_counter_map_reset(counter_map: *const ConcurrentCounterMap) {
+    let counter_map = unsafe { counter_map.as_ref().unwrap() };
+    counter_map.reset();
+}
+
+pub const CONCURRENT_COUNTER_MAP_SIZE: usize = 40;
+
+#[test]
+fn test_concurrent_counter_map() {
+    assert_eq!(
+        CONCURRENT_COUNTER_MAP_SIZE,
+        std::mem::size_of::<ConcurrentCounterMap>(),
+        "size mismatch"
+    );
+    assert!(crate::is_sync_and_send::<ConcurrentCounterMap>());
+
+    use crate::test_helpers::fix;
+
+    let counter_map = std::sync::Arc::new(ConcurrentCounterMap::new());
+    let threads = (0..4)
+        .map(|_| {
+            let counter_map = std::sync::Arc::clone(&counter_map);
+            std::thread::spawn(move || {
+                for n in 0..1_000 {
+                    counter_map.increment(fix(200), 1);
+                    if n % 10 == 0 {
+                        counter_map.increment(fix(404), 1);
+                    }
+                }
+                counter_map.increment(fix(500), 1);
+            })
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    assert_eq!(counter_map.get(fix(200)), 4_000);
+    assert_eq!(counter_map.get(fix(302)), 0);
+    assert_eq!(counter_map.top_n(2), [(fix(200), 4_000), (fix(404), 400)]);
+    assert_eq!(counter_map.len(), 3);
+    counter_map.reset();
+    assert_eq!(counter_map.len(), 0);
+}
+
+#[test]
+fn test_concurrent_counter_map_mark_under_write_lock() {
+    use std::sync::atomic::AtomicUsize;
+
+    use crate::test_helpers::fix;
+    static MARKED: AtomicUsize = AtomicUsize::new(0);
+    extern "C" fn count(_: c_ulong) {
+        MARKED.fetch_add(1, Ordering::Relaxed);
+    }
+
+    let counter_map = ConcurrentCounterMap::new();
+    for n in 0..100 {
+        counter_map.increment(fix(n), n);
+    }
+    let _guards = counter_map
+        .map
+        .shards()
+        .iter()
+        .map(|shard| shard.write())
+        .collect::<Vec<_>>();
+    counter_map.mark(count);
+    assert_eq!(MARKED.load(Ordering::Relaxed), 100);
+}
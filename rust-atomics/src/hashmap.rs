@@ -1,24 +1,104 @@
-use std::ffi::{c_int, c_ulong};
+use crate::{MpmcQueue, rvalue};
+use dashmap::{SharedValue, mapref::entry::Entry};
+use std::{
+    ffi::{c_int, c_ulong},
+    hash::{Hash, Hasher},
+};
 
-#[derive(Debug)]
-struct RubyHashEql(c_ulong);
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RubyHashEql(pub(crate) c_ulong);
 
 impl PartialEq for RubyHashEql {
     fn eq(&self, other: &Self) -> bool {
-        unsafe { rb_eql(self.0, other.0) != 0 }
+        match unsafe { rvalue::eql_natively(self.0, other.0) } {
+            Some(eql) => eql,
+            None => unsafe { rb_eql(self.0, other.0) != 0 },
+        }
     }
 }
 impl Eq for RubyHashEql {}
 
 impl std::hash::Hash for RubyHashEql {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        if unsafe { rvalue::hash_natively(self.0, state) } {
+            return;
+        }
         let ruby_hash = unsafe { rb_hash(self.0) };
         ruby_hash.hash(state);
     }
 }
 
+// Keys of the maps carry a hash computed before any shard is locked,
+// so growing or rehashing a shard never calls back into Ruby halfway through.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct HashedKey {
+    pub(crate) key: RubyHashEql,
+    pub(crate) hash: u64,
+}
+
+impl HashedKey {
+    pub(crate) fn new(key: c_ulong) -> Self {
+        let key = RubyHashEql(key);
+        let mut hasher = std::hash::DefaultHasher::new();
+        key.hash(&mut hasher);
+        Self {
+            key,
+            hash: hasher.finish(),
+        }
+    }
+}
+
+impl PartialEq for HashedKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.hash == other.hash && self.key == other.key
+    }
+}
+impl Eq for HashedKey {}
+
+impl std::hash::Hash for HashedKey {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
+struct Subscriber {
+    queue_obj: c_ulong,
+    queue: *const MpmcQueue,
+}
+
+unsafe impl Send for Subscriber {}
+unsafe impl Sync for Subscriber {}
+
 pub struct ConcurrentHashMap {
-    map: dashmap::DashMap<RubyHashEql, c_ulong>,
+    map: dashmap::DashMap<HashedKey, c_ulong>,
+    shards_count: usize,
+    subscribers: parking_lot::RwLock<Vec<Subscriber>>,
+}
+
+#[repr(C)]
+pub struct ConcurrentHashMapMemoryUsage {
+    pub size: usize,
+    pub capacity: usize,
+    pub shards: usize,
+    // approximate size of the shards' hash tables, buckets plus control bytes
+    pub bytes: usize,
+}
+
+pub const CONCURRENT_HASH_MAP_TX_GET: u8 = 0;
+pub const CONCURRENT_HASH_MAP_TX_SET: u8 = 1;
+pub const CONCURRENT_HASH_MAP_TX_DELETE: u8 = 2;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct ConcurrentHashMapTxOp {
+    pub kind: u8,
+    pub key: c_ulong,
+    pub value: c_ulong,
+}
+
+// same as the default of DashMap
+fn default_shards_count() -> usize {
+    (std::thread::available_parallelism().map_or(1, usize::from) * 4).next_power_of_two()
 }
 
 unsafe extern "C" {
@@ -26,54 +106,413 @@ unsafe extern "C" {
     fn rb_eql(lhs: c_ulong, rhs: c_ulong) -> c_int;
 }
 
+// A Ruby block called through `rb_protect`. It runs under a shard lock, so it
+// must not longjmp over Rust frames: a raise, `break` or `throw` is reported
+// through a non-zero state instead, and C resumes it once the lock is released.
+type ProtectedBlock = extern "C" fn(c_ulong, *mut c_int) -> c_ulong;
+
+fn call_protected(f: ProtectedBlock, value: c_ulong) -> Result<c_ulong, c_int> {
+    let mut state = 0;
+    let result = f(value, &mut state);
+    if state == 0 { Ok(result) } else { Err(state) }
+}
+
 impl ConcurrentHashMap {
     fn new() -> Self {
+        Self::with_capacity_and_shards(0, 0)
+    }
+
+    fn with_capacity_and_shards(capacity: usize, shards_count: usize) -> Self {
+        let shards_count = if shards_count == 0 {
+            default_shards_count()
+        } else {
+            shards_count
+        };
         Self {
-            map: dashmap::DashMap::new(),
+            map: dashmap::DashMap::with_capacity_and_shard_amount(capacity, shards_count),
+            shards_count,
+            subscribers: parking_lot::RwLock::new(vec![]),
         }
     }
 
     fn get(&self, key: c_ulong) -> Option<c_ulong> {
-        let key = RubyHashEql(key);
+        let key = HashedKey::new(key);
         self.map.get(&key).map(|v| *v)
     }
 
     fn set(&self, key: c_ulong, value: c_ulong) {
-        let key = RubyHashEql(key);
+        let key = HashedKey::new(key);
         self.map.insert(key, value);
     }
 
-    fn clear(&self) {
-        self.map.clear()
+    fn swap(&self, key: c_ulong, value: c_ulong) -> Option<c_ulong> {
+        let key = HashedKey::new(key);
+        self.map.insert(key, value)
     }
 
-    fn fetch_and_modify(&self, key: c_ulong, f: extern "C" fn(c_ulong) -> c_ulong) {
-        let key = RubyHashEql(key);
-        self.map.alter(&key, |_, v| f(v));
+    // Clears shards one by one, copying their pairs into `removed` if it's given.
+    // Stops at the first shard whose pairs don't fit, returns how many pairs
+    // were removed and whether all shards have been cleared.
+    fn clear(&self, mut removed: Option<&mut [c_ulong]>) -> (usize, bool) {
+        let mut len = 0;
+        for shard in self.map.shards() {
+            let mut table = shard.write();
+            if let Some(removed) = removed.as_deref_mut() {
+                let out = &mut removed[len * 2..];
+                if table.len() * 2 > out.len() {
+                    return (len, false);
+                }
+                for (bucket, out) in unsafe { table.iter() }.zip(out.chunks_exact_mut(2)) {
+                    let (key, value) = unsafe { bucket.as_ref() };
+                    out.copy_from_slice(&[key.key.0, *value.get()]);
+                }
+            }
+            len += table.len();
+            table.clear();
+        }
+        (len, true)
+    }
+
+    fn delete(&self, key: c_ulong) -> Option<c_ulong> {
+        let key = HashedKey::new(key);
+        self.map.remove(&key).map(|(_, v)| v)
+    }
+
+    fn contains_key(&self, key: c_ulong) -> bool {
+        let key = HashedKey::new(key);
+        self.map.contains_key(&key)
+    }
+
+    fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    fn replace_if(&self, key: c_ulong, expected: c_ulong, new_value: c_ulong) -> bool {
+        match self.map.get_mut(&HashedKey::new(key)) {
+            Some(mut value) if *value == expected => {
+                *value = new_value;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn delete_if(&self, key: c_ulong, expected: c_ulong) -> bool {
+        self.map
+            .remove_if(&HashedKey::new(key), |_, value| *value == expected)
+            .is_some()
+    }
+
+    // Shards touched by `ops` are locked in ascending order, so concurrent
+    // transactions can't deadlock. Returns the value of every key right
+    // before its op was applied.
+    fn transaction(&self, ops: &[ConcurrentHashMapTxOp]) -> Vec<Option<c_ulong>> {
+        // rb_hash may call back into Ruby, so keys are hashed before locking
+        let keys = ops
+            .iter()
+            .map(|op| HashedKey::new(op.key))
+            .collect::<Vec<_>>();
+        let hashes = keys
+            .iter()
+            .map(|key| self.map.hash_usize(key))
+            .collect::<Vec<_>>();
+        let mut shard_idxs = hashes
+            .iter()
+            .map(|hash| self.map.determine_shard(*hash))
+            .collect::<Vec<_>>();
+        shard_idxs.sort_unstable();
+        shard_idxs.dedup();
+        let mut shards = shard_idxs
+            .iter()
+            .map(|idx| self.map.shards()[*idx].write())
+            .collect::<Vec<_>>();
+
+        let hasher = |(key, _): &(HashedKey, SharedValue<c_ulong>)| self.map.hash_usize(key) as u64;
+        ops.iter()
+            .zip(keys)
+            .zip(hashes)
+            .map(|((op, key), hash)| {
+                let shard_idx = self.map.determine_shard(hash);
+                let table = &mut *shards[shard_idxs.binary_search(&shard_idx).unwrap()];
+                let bucket = table.find(hash as u64, |(k, _)| *k == key);
+                let previous = bucket
+                    .as_ref()
+                    .map(|bucket| *unsafe { bucket.as_ref() }.1.get());
+                match (op.kind, bucket) {
+                    (CONCURRENT_HASH_MAP_TX_SET, Some(bucket)) => {
+                        unsafe { bucket.as_mut() }.1 = SharedValue::new(op.value);
+                    }
+                    (CONCURRENT_HASH_MAP_TX_SET, None) => {
+                        table.insert(hash as u64, (key, SharedValue::new(op.value)), hasher);
+                    }
+                    (CONCURRENT_HASH_MAP_TX_DELETE, Some(bucket)) => {
+                        unsafe { table.remove(bucket) };
+                    }
+                    (CONCURRENT_HASH_MAP_TX_GET | CONCURRENT_HASH_MAP_TX_DELETE, _) => {}
+                    (kind, _) => panic!("unknown transaction op {kind}"),
+                }
+                previous
+            })
+            .collect()
+    }
+
+    // Returns the previous value of every key
+    fn set_many(&self, keys: &[c_ulong], values: &[c_ulong]) -> Vec<Option<c_ulong>> {
+        keys.iter()
+            .zip(values)
+            .map(|(&key, &value)| self.swap(key, value))
+            .collect()
+    }
+
+    fn get_many(&self, keys: &[c_ulong], out: &mut [c_ulong], fallback: c_ulong) {
+        for (&key, out) in keys.iter().zip(out) {
+            *out = self.get(key).unwrap_or(fallback);
+        }
+    }
+
+    // Returns the removed value of every key
+    fn delete_many(&self, keys: &[c_ulong]) -> Vec<Option<c_ulong>> {
+        keys.iter().map(|&key| self.delete(key)).collect()
+    }
+
+    // `other` is copied through a snapshot, so that merging a map into itself
+    // doesn't take the same shard lock twice. Returns every merged pair with
+    // the value it replaced; if there are more than `cap` pairs nothing is
+    // merged and their number is returned instead.
+    fn merge(
+        &self,
+        other: &ConcurrentHashMap,
+        cap: usize,
+    ) -> Result<Vec<(c_ulong, Option<c_ulong>, c_ulong)>, usize> {
+        let pairs = other.snapshot();
+        if pairs.len() > cap {
+            return Err(pairs.len());
+        }
+        Ok(pairs
+            .into_iter()
+            .map(|(key, value)| (key, self.swap(key, value), value))
+            .collect())
+    }
+
+    fn subscribe(&self, queue_obj: c_ulong, queue: *const MpmcQueue) {
+        self.subscribers
+            .write()
+            .push(Subscriber { queue_obj, queue });
+    }
+
+    fn unsubscribe(&self, queue_obj: c_ulong) -> bool {
+        let mut subscribers = self.subscribers.write();
+        let len = subscribers.len();
+        subscribers.retain(|subscriber| subscriber.queue_obj != queue_obj);
+        subscribers.len() != len
+    }
+
+    fn has_subscribers(&self) -> bool {
+        !self.subscribers.read().is_empty()
+    }
+
+    // Writers never block on slow subscribers,
+    // events that don't fit into a subscriber's queue are dropped.
+    fn publish(&self, event: c_ulong) -> usize {
+        self.subscribers
+            .read()
+            .iter()
+            .filter(|subscriber| {
+                let queue = unsafe { subscriber.queue.as_ref().unwrap() };
+                !queue.try_push(event)
+            })
+            .count()
+    }
+
+    fn shrink_to_fit(&self) {
+        self.map.shrink_to_fit()
+    }
+
+    fn memory_usage(&self) -> ConcurrentHashMapMemoryUsage {
+        let capacity = self.map.capacity();
+        let bucket_size = std::mem::size_of::<(HashedKey, c_ulong)>() + 1;
+        ConcurrentHashMapMemoryUsage {
+            size: self.map.len(),
+            capacity,
+            shards: self.shards_count,
+            bytes: capacity * bucket_size,
+        }
+    }
+
+    // Returns the old and the new value, or `None` if there's no such key
+    fn fetch_and_modify(
+        &self,
+        key: c_ulong,
+        f: ProtectedBlock,
+    ) -> Result<Option<(c_ulong, c_ulong)>, c_int> {
+        let Some(mut value) = self.map.get_mut(&HashedKey::new(key)) else {
+            return Ok(None);
+        };
+        let old_value = *value;
+        *value = call_protected(f, old_value)?;
+        Ok(Some((old_value, *value)))
+    }
+
+    // Returns the existing value, if any, and the value that ends up in the map
+    fn put_if_absent(&self, key: c_ulong, value: c_ulong) -> (Option<c_ulong>, c_ulong) {
+        match self.map.entry(HashedKey::new(key)) {
+            Entry::Occupied(entry) => (Some(*entry.get()), *entry.get()),
+            Entry::Vacant(entry) => (None, *entry.insert(value)),
+        }
+    }
+
+    fn compute_if_absent(
+        &self,
+        key: c_ulong,
+        f: ProtectedBlock,
+    ) -> Result<(Option<c_ulong>, c_ulong), c_int> {
+        match self.map.entry(HashedKey::new(key)) {
+            Entry::Occupied(entry) => Ok((Some(*entry.get()), *entry.get())),
+            Entry::Vacant(entry) => Ok((None, *entry.insert(call_protected(f, key)?))),
+        }
+    }
+
+    fn snapshot(&self) -> Vec<(c_ulong, c_ulong)> {
+        self.map
+            .iter()
+            .map(|pair| (pair.key().key.0, *pair.value()))
+            .collect()
+    }
+
+    // Returns the old and the new value, `absent` stands for a missing key
+    fn upsert(
+        &self,
+        key: c_ulong,
+        absent: c_ulong,
+        f: ProtectedBlock,
+    ) -> Result<(c_ulong, c_ulong), c_int> {
+        match self.map.entry(HashedKey::new(key)) {
+            Entry::Occupied(mut entry) => {
+                let old_value = *entry.get();
+                let new_value = call_protected(f, old_value)?;
+                if new_value == absent {
+                    entry.remove();
+                } else {
+                    entry.insert(new_value);
+                }
+                Ok((old_value, new_value))
+            }
+            Entry::Vacant(entry) => {
+                let new_value = call_protected(f, absent)?;
+                if new_value != absent {
+                    entry.insert(new_value);
+                }
+                Ok((absent, new_value))
+            }
+        }
+    }
+
+    // Unlike `upsert`, `f` runs without any shard lock held, so it may freely
+    // read or write the map. Its result is committed only if the key still
+    // holds the value `f` has seen, otherwise `f` is re-run on the new value.
+    fn compute(
+        &self,
+        key: c_ulong,
+        absent: c_ulong,
+        f: extern "C" fn(c_ulong) -> c_ulong,
+    ) -> (c_ulong, c_ulong) {
+        let key = HashedKey::new(key);
+        loop {
+            let seen = self.map.get(&key).map(|value| *value);
+            let old_value = seen.unwrap_or(absent);
+            let new_value = f(old_value);
+            match self.map.entry(key) {
+                Entry::Occupied(mut entry) if Some(*entry.get()) == seen => {
+                    if new_value == absent {
+                        entry.remove();
+                    } else {
+                        entry.insert(new_value);
+                    }
+                }
+                Entry::Vacant(entry) if seen.is_none() => {
+                    if new_value != absent {
+                        entry.insert(new_value);
+                    }
+                }
+                _ => continue,
+            }
+            return (old_value, new_value);
+        }
     }
 
     fn mark(&self, f: extern "C" fn(c_ulong)) {
-        for pair in self.map.iter() {
-            f(pair.key().0);
-            f(*pair.value());
+        mark_shards(&self.map, |key, value| {
+            f(key.key.0);
+            f(*value);
+        });
+        // never locked across Ruby calls
+        for subscriber in self.subscribers.read().iter() {
+            f(subscriber.queue_obj);
+        }
+    }
+}
+
+// GC must never wait on a shard lock: its holder may be the very thread
+// that triggered GC from a Ruby callback (`rb_eql` on a key, or a block of
+// `fetch_and_modify` and friends). A shard that is write-locked during GC
+// belongs to a mutator parked in Ruby code, and maps only call into Ruby while
+// their tables are consistent, so such a shard can be safely read without the lock.
+pub(crate) fn mark_shards<K: Eq + Hash, V>(
+    map: &dashmap::DashMap<K, V>,
+    mut f: impl FnMut(&K, &V),
+) {
+    for shard in map.shards() {
+        let read_guard = shard.try_read();
+        let table = match &read_guard {
+            Some(table) => &**table,
+            None => unsafe { &*shard.data_ptr() },
+        };
+        for bucket in unsafe { table.iter() } {
+            let (key, value) = unsafe { bucket.as_ref() };
+            f(key, value.get());
         }
     }
 }
 
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn concurrent_hash_map_init(hashmap: *mut ConcurrentHashMap) {
+pub unsafe extern "C" fn concurrent_hash_map_alloc(hashmap: *mut ConcurrentHashMap) {
     unsafe { hashmap.write(ConcurrentHashMap::new()) }
 }
 
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_hash_map_init(
+    hashmap: *mut ConcurrentHashMap,
+    capacity: usize,
+    shards_count: usize,
+) {
+    assert!(shards_count == 0 || (shards_count > 1 && shards_count.is_power_of_two()));
+    let hashmap = unsafe { hashmap.as_mut().unwrap() };
+    *hashmap = ConcurrentHashMap::with_capacity_and_shards(capacity, shards_count);
+}
+
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn concurrent_hash_map_drop(hashmap: *mut ConcurrentHashMap) {
     unsafe { std::ptr::drop_in_place(hashmap) };
 }
 
+// `removed` may be null, otherwise it's filled with up to `cap` removed pairs
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn concurrent_hash_map_clear(hashmap: *const ConcurrentHashMap) {
+pub unsafe extern "C" fn concurrent_hash_map_clear(
+    hashmap: *const ConcurrentHashMap,
+    removed: *mut c_ulong,
+    cap: usize,
+    len: *mut usize,
+) -> bool {
     let hashmap = unsafe { hashmap.as_ref().unwrap() };
-    hashmap.clear();
+    let removed =
+        (!removed.is_null()).then(|| unsafe { std::slice::from_raw_parts_mut(removed, cap * 2) });
+    let (removed_len, done) = hashmap.clear(removed);
+    unsafe { len.write(removed_len) };
+    done
 }
 
 #[unsafe(no_mangle)]
@@ -96,6 +535,210 @@ pub unsafe extern "C" fn concurrent_hash_map_set(
     hashmap.set(key, value);
 }
 
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_hash_map_swap(
+    hashmap: *const ConcurrentHashMap,
+    key: c_ulong,
+    value: c_ulong,
+    fallback: c_ulong,
+) -> c_ulong {
+    let hashmap = unsafe { hashmap.as_ref().unwrap() };
+    hashmap.swap(key, value).unwrap_or(fallback)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_hash_map_delete(
+    hashmap: *const ConcurrentHashMap,
+    key: c_ulong,
+    fallback: c_ulong,
+) -> c_ulong {
+    let hashmap = unsafe { hashmap.as_ref().unwrap() };
+    hashmap.delete(key).unwrap_or(fallback)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_hash_map_contains_key(
+    hashmap: *const ConcurrentHashMap,
+    key: c_ulong,
+) -> bool {
+    let hashmap = unsafe { hashmap.as_ref().unwrap() };
+    hashmap.contains_key(key)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_hash_map_size(hashmap: *const ConcurrentHashMap) -> usize {
+    let hashmap = unsafe { hashmap.as_ref().unwrap() };
+    hashmap.len()
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_hash_map_is_empty(hashmap: *const ConcurrentHashMap) -> bool {
+    let hashmap = unsafe { hashmap.as_ref().unwrap() };
+    hashmap.is_empty()
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_hash_map_replace_if(
+    hashmap: *const ConcurrentHashMap,
+    key: c_ulong,
+    expected: c_ulong,
+    new_value: c_ulong,
+) -> bool {
+    let hashmap = unsafe { hashmap.as_ref().unwrap() };
+    hashmap.replace_if(key, expected, new_value)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_hash_map_delete_if(
+    hashmap: *const ConcurrentHashMap,
+    key: c_ulong,
+    expected: c_ulong,
+) -> bool {
+    let hashmap = unsafe { hashmap.as_ref().unwrap() };
+    hashmap.delete_if(key, expected)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_hash_map_transaction(
+    hashmap: *const ConcurrentHashMap,
+    ops: *const ConcurrentHashMapTxOp,
+    len: usize,
+    pre_images: *mut c_ulong,
+    fallback: c_ulong,
+) {
+    let hashmap = unsafe { hashmap.as_ref().unwrap() };
+    let ops = unsafe { std::slice::from_raw_parts(ops, len) };
+    let pre_images = unsafe { std::slice::from_raw_parts_mut(pre_images, len) };
+    for (out, previous) in pre_images.iter_mut().zip(hashmap.transaction(ops)) {
+        *out = previous.unwrap_or(fallback);
+    }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_hash_map_set_many(
+    hashmap: *const ConcurrentHashMap,
+    keys: *const c_ulong,
+    values: *const c_ulong,
+    len: usize,
+    old_values: *mut c_ulong,
+    fallback: c_ulong,
+) {
+    let hashmap = unsafe { hashmap.as_ref().unwrap() };
+    let keys = unsafe { std::slice::from_raw_parts(keys, len) };
+    let values = unsafe { std::slice::from_raw_parts(values, len) };
+    let old_values = unsafe { std::slice::from_raw_parts_mut(old_values, len) };
+    for (out, old_value) in old_values.iter_mut().zip(hashmap.set_many(keys, values)) {
+        *out = old_value.unwrap_or(fallback);
+    }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_hash_map_get_many(
+    hashmap: *const ConcurrentHashMap,
+    keys: *const c_ulong,
+    len: usize,
+    out: *mut c_ulong,
+    fallback: c_ulong,
+) {
+    let hashmap = unsafe { hashmap.as_ref().unwrap() };
+    let keys = unsafe { std::slice::from_raw_parts(keys, len) };
+    let out = unsafe { std::slice::from_raw_parts_mut(out, len) };
+    hashmap.get_many(keys, out, fallback);
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_hash_map_delete_many(
+    hashmap: *const ConcurrentHashMap,
+    keys: *const c_ulong,
+    len: usize,
+    old_values: *mut c_ulong,
+    fallback: c_ulong,
+) -> usize {
+    let hashmap = unsafe { hashmap.as_ref().unwrap() };
+    let keys = unsafe { std::slice::from_raw_parts(keys, len) };
+    let old_values = unsafe { std::slice::from_raw_parts_mut(old_values, len) };
+    let mut deleted = 0;
+    for (out, old_value) in old_values.iter_mut().zip(hashmap.delete_many(keys)) {
+        deleted += usize::from(old_value.is_some());
+        *out = old_value.unwrap_or(fallback);
+    }
+    deleted
+}
+
+// Writes (key, old value, new value) triples, returns how many pairs were
+// merged or, if they don't fit into `cap`, how many there are to merge
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_hash_map_merge(
+    hashmap: *const ConcurrentHashMap,
+    other: *const ConcurrentHashMap,
+    merged: *mut c_ulong,
+    cap: usize,
+    fallback: c_ulong,
+) -> usize {
+    let hashmap = unsafe { hashmap.as_ref().unwrap() };
+    let other = unsafe { other.as_ref().unwrap() };
+    match hashmap.merge(other, cap) {
+        Ok(triples) => {
+            let merged = unsafe { std::slice::from_raw_parts_mut(merged, triples.len() * 3) };
+            for (out, (key, old_value, value)) in merged.chunks_exact_mut(3).zip(&triples) {
+                out.copy_from_slice(&[*key, old_value.unwrap_or(fallback), *value]);
+            }
+            triples.len()
+        }
+        Err(len) => len,
+    }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_hash_map_subscribe(
+    hashmap: *const ConcurrentHashMap,
+    queue_obj: c_ulong,
+    queue: *const MpmcQueue,
+) {
+    let hashmap = unsafe { hashmap.as_ref().unwrap() };
+    hashmap.subscribe(queue_obj, queue);
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_hash_map_unsubscribe(
+    hashmap: *const ConcurrentHashMap,
+    queue_obj: c_ulong,
+) -> bool {
+    let hashmap = unsafe { hashmap.as_ref().unwrap() };
+    hashmap.unsubscribe(queue_obj)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_hash_map_has_subscribers(
+    hashmap: *const ConcurrentHashMap,
+) -> bool {
+    let hashmap = unsafe { hashmap.as_ref().unwrap() };
+    hashmap.has_subscribers()
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_hash_map_publish(
+    hashmap: *const ConcurrentHashMap,
+    event: c_ulong,
+) -> usize {
+    let hashmap = unsafe { hashmap.as_ref().unwrap() };
+    hashmap.publish(event)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_hash_map_shrink_to_fit(hashmap: *const ConcurrentHashMap) {
+    let hashmap = unsafe { hashmap.as_ref().unwrap() };
+    hashmap.shrink_to_fit();
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_hash_map_memory_usage(
+    hashmap: *const ConcurrentHashMap,
+) -> ConcurrentHashMapMemoryUsage {
+    let hashmap = unsafe { hashmap.as_ref().unwrap() };
+    hashmap.memory_usage()
+}
+
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn concurrent_hash_map_mark(
     hashmap: *const ConcurrentHashMap,
@@ -105,17 +748,144 @@ pub unsafe extern "C" fn concurrent_hash_map_mark(
     hashmap.mark(f);
 }
 
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_hash_map_put_if_absent(
+    hashmap: *const ConcurrentHashMap,
+    key: c_ulong,
+    value: c_ulong,
+    old_value: *mut c_ulong,
+    fallback: c_ulong,
+) -> c_ulong {
+    let hashmap = unsafe { hashmap.as_ref().unwrap() };
+    let (old, new) = hashmap.put_if_absent(key, value);
+    unsafe { old_value.write(old.unwrap_or(fallback)) };
+    new
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_hash_map_compute_if_absent(
+    hashmap: *const ConcurrentHashMap,
+    key: c_ulong,
+    f: ProtectedBlock,
+    old_value: *mut c_ulong,
+    fallback: c_ulong,
+    state: *mut c_int,
+) -> c_ulong {
+    let hashmap = unsafe { hashmap.as_ref().unwrap() };
+    let (old, new) = hashmap.compute_if_absent(key, f).unwrap_or_else(|err| {
+        unsafe { state.write(err) };
+        (None, fallback)
+    });
+    unsafe { old_value.write(old.unwrap_or(fallback)) };
+    new
+}
+
+unsafe fn write_snapshot<T: Copy>(items: &[T], out: *mut T, cap: usize) -> usize {
+    if items.len() <= cap {
+        unsafe { std::ptr::copy_nonoverlapping(items.as_ptr(), out, items.len()) };
+    }
+    items.len()
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_hash_map_snapshot(
+    hashmap: *const ConcurrentHashMap,
+    pairs: *mut c_ulong,
+    cap: usize,
+) -> usize {
+    let hashmap = unsafe { hashmap.as_ref().unwrap() };
+    let pairs_snapshot = hashmap
+        .snapshot()
+        .into_iter()
+        .flat_map(|(key, value)| [key, value])
+        .collect::<Vec<_>>();
+    unsafe { write_snapshot(&pairs_snapshot, pairs, cap * 2) / 2 }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_hash_map_keys(
+    hashmap: *const ConcurrentHashMap,
+    keys: *mut c_ulong,
+    cap: usize,
+) -> usize {
+    let hashmap = unsafe { hashmap.as_ref().unwrap() };
+    let keys_snapshot = hashmap
+        .snapshot()
+        .into_iter()
+        .map(|(key, _)| key)
+        .collect::<Vec<_>>();
+    unsafe { write_snapshot(&keys_snapshot, keys, cap) }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_hash_map_values(
+    hashmap: *const ConcurrentHashMap,
+    values: *mut c_ulong,
+    cap: usize,
+) -> usize {
+    let hashmap = unsafe { hashmap.as_ref().unwrap() };
+    let values_snapshot = hashmap
+        .snapshot()
+        .into_iter()
+        .map(|(_, value)| value)
+        .collect::<Vec<_>>();
+    unsafe { write_snapshot(&values_snapshot, values, cap) }
+}
+
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn concurrent_hash_map_fetch_and_modify(
     hashmap: *const ConcurrentHashMap,
     key: c_ulong,
+    f: ProtectedBlock,
+    old_value: *mut c_ulong,
+    fallback: c_ulong,
+    state: *mut c_int,
+) -> c_ulong {
+    let hashmap = unsafe { hashmap.as_ref().unwrap() };
+    let (old, new) = hashmap
+        .fetch_and_modify(key, f)
+        .unwrap_or_else(|err| {
+            unsafe { state.write(err) };
+            None
+        })
+        .unzip();
+    unsafe { old_value.write(old.unwrap_or(fallback)) };
+    new.unwrap_or(fallback)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_hash_map_upsert(
+    hashmap: *const ConcurrentHashMap,
+    key: c_ulong,
+    absent: c_ulong,
+    f: ProtectedBlock,
+    old_value: *mut c_ulong,
+    state: *mut c_int,
+) -> c_ulong {
+    let hashmap = unsafe { hashmap.as_ref().unwrap() };
+    let (old, new) = hashmap.upsert(key, absent, f).unwrap_or_else(|err| {
+        unsafe { state.write(err) };
+        (absent, absent)
+    });
+    unsafe { old_value.write(old) };
+    new
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_hash_map_compute(
+    hashmap: *const ConcurrentHashMap,
+    key: c_ulong,
+    absent: c_ulong,
     f: extern "C" fn(c_ulong) -> c_ulong,
-) {
+    old_value: *mut c_ulong,
+) -> c_ulong {
     let hashmap = unsafe { hashmap.as_ref().unwrap() };
-    hashmap.fetch_and_modify(key, f);
+    let (old, new) = hashmap.compute(key, absent, f);
+    unsafe { old_value.write(old) };
+    new
 }
 
-pub const CONCURRENT_HASH_MAP_SIZE: usize = 40;
+pub const CONCURRENT_HASH_MAP_SIZE: usize = 80;
 
 #[test]
 fn test_concurrent_hash_map() {
@@ -126,4 +896,331 @@ fn test_concurrent_hash_map() {
     );
 
     assert!(crate::is_sync_and_send::<ConcurrentHashMap>());
+
+    use crate::test_helpers::{fix, unfix};
+    const ABSENT: c_ulong = c_ulong::MAX;
+    extern "C" fn square(value: c_ulong, _state: *mut c_int) -> c_ulong {
+        fix(unfix(value) * unfix(value))
+    }
+    extern "C" fn decrement_until_absent(value: c_ulong, _state: *mut c_int) -> c_ulong {
+        match value {
+            ABSENT => fix(1),
+            value if value == fix(1) => ABSENT,
+            value => fix(unfix(value) - 1),
+        }
+    }
+
+    let hashmap = ConcurrentHashMap::new();
+    assert!(hashmap.is_empty());
+    hashmap.set(fix(1), fix(10));
+    assert!(hashmap.contains_key(fix(1)));
+    assert!(!hashmap.contains_key(fix(2)));
+    assert_eq!(hashmap.len(), 1);
+    assert_eq!(hashmap.delete(fix(1)), Some(fix(10)));
+    assert_eq!(hashmap.delete(fix(1)), None);
+    assert!(hashmap.is_empty());
+
+    // existing values win
+    assert_eq!(hashmap.put_if_absent(fix(2), fix(20)), (None, fix(20)));
+    assert_eq!(
+        hashmap.put_if_absent(fix(2), fix(30)),
+        (Some(fix(20)), fix(20))
+    );
+    assert_eq!(
+        hashmap.compute_if_absent(fix(3), square),
+        Ok((None, fix(9)))
+    );
+    assert_eq!(
+        hashmap.compute_if_absent(fix(3), square),
+        Ok((Some(fix(9)), fix(9)))
+    );
+    assert_eq!(
+        hashmap.compute_if_absent(fix(2), square),
+        Ok((Some(fix(20)), fix(20)))
+    );
+
+    // returning the sentinel deletes the key
+    assert_eq!(
+        hashmap.upsert(fix(4), ABSENT, decrement_until_absent),
+        Ok((ABSENT, fix(1)))
+    );
+    assert_eq!(hashmap.get(fix(4)), Some(fix(1)));
+    assert_eq!(
+        hashmap.upsert(fix(4), ABSENT, decrement_until_absent),
+        Ok((fix(1), ABSENT))
+    );
+    assert!(!hashmap.contains_key(fix(4)));
+    hashmap.set(fix(4), fix(3));
+    assert_eq!(
+        hashmap.upsert(fix(4), ABSENT, decrement_until_absent),
+        Ok((fix(3), fix(2)))
+    );
+    assert_eq!(hashmap.get(fix(4)), Some(fix(2)));
+
+    assert_eq!(
+        hashmap.fetch_and_modify(fix(4), square),
+        Ok(Some((fix(2), fix(4))))
+    );
+    assert_eq!(hashmap.fetch_and_modify(fix(5), square), Ok(None));
+    assert!(!hashmap.contains_key(fix(5)));
+}
+
+#[test]
+fn test_concurrent_hash_map_raising_block() {
+    use crate::test_helpers::fix;
+    const TAG_RAISE: c_int = 6;
+    extern "C" fn raise(_value: c_ulong, state: *mut c_int) -> c_ulong {
+        unsafe { state.write(TAG_RAISE) };
+        0
+    }
+
+    let hashmap = ConcurrentHashMap::with_capacity_and_shards(0, 2);
+    hashmap.set(fix(1), fix(10));
+    assert_eq!(hashmap.fetch_and_modify(fix(1), raise), Err(TAG_RAISE));
+    assert_eq!(hashmap.compute_if_absent(fix(2), raise), Err(TAG_RAISE));
+    assert_eq!(hashmap.upsert(fix(1), 0, raise), Err(TAG_RAISE));
+    assert_eq!(hashmap.upsert(fix(2), 0, raise), Err(TAG_RAISE));
+
+    // nothing is changed and no shard is left locked
+    assert_eq!(hashmap.snapshot(), [(fix(1), fix(10))]);
+    assert!(
+        hashmap
+            .map
+            .shards()
+            .iter()
+            .all(|shard| shard.try_write().is_some())
+    );
+}
+
+#[test]
+fn test_concurrent_hash_map_capacity() {
+    use crate::test_helpers::fix;
+
+    let hashmap = ConcurrentHashMap::with_capacity_and_shards(1_000, 4);
+    let usage = hashmap.memory_usage();
+    assert_eq!(usage.shards, 4);
+    assert!(usage.capacity >= 1_000);
+    assert!(usage.bytes >= usage.capacity * 16);
+
+    for n in 0..10 {
+        hashmap.set(fix(n), fix(n));
+    }
+    hashmap.shrink_to_fit();
+    let usage = hashmap.memory_usage();
+    assert_eq!(usage.size, 10);
+    assert!(usage.capacity < 1_000);
+}
+
+#[test]
+fn test_concurrent_hash_map_batch() {
+    use crate::test_helpers::fix;
+    const QNIL: c_ulong = 0x08;
+
+    let hashmap = ConcurrentHashMap::with_capacity_and_shards(0, 4);
+    assert_eq!(
+        hashmap.set_many(&[fix(1), fix(2), fix(1)], &[fix(10), fix(20), fix(11)]),
+        [None, None, Some(fix(10))]
+    );
+    assert_eq!(hashmap.len(), 2);
+
+    let mut out = [0; 2];
+    hashmap.get_many(&[fix(2), fix(4)], &mut out, QNIL);
+    assert_eq!(out, [fix(20), QNIL]);
+
+    assert_eq!(
+        hashmap.delete_many(&[fix(1), fix(4)]),
+        [Some(fix(11)), None]
+    );
+    assert_eq!(hashmap.len(), 1);
+
+    let other = ConcurrentHashMap::new();
+    other.set_many(&[fix(2), fix(3)], &[fix(200), fix(300)]);
+    assert_eq!(hashmap.merge(&other, 1), Err(2));
+    assert_eq!(hashmap.get(fix(3)), None);
+    let mut merged = hashmap.merge(&other, 2).unwrap();
+    merged.sort();
+    assert_eq!(
+        merged,
+        [(fix(2), Some(fix(20)), fix(200)), (fix(3), None, fix(300))]
+    );
+    assert_eq!(
+        hashmap.merge(&hashmap, 2).unwrap().len(),
+        2,
+        "merging a map into itself"
+    );
+
+    for n in 0..100 {
+        hashmap.set(fix(n), fix(n));
+    }
+    // shards that don't fit are left as is
+    let mut removed = [0; 20];
+    let (len, done) = hashmap.clear(Some(&mut removed));
+    assert!(!done);
+    assert_eq!(hashmap.len(), 100 - len);
+    let mut removed = [0; 200];
+    let (rest, done) = hashmap.clear(Some(&mut removed));
+    assert!(done);
+    assert_eq!(len + rest, 100);
+    assert!(hashmap.is_empty());
+    assert!(
+        removed[..rest * 2]
+            .chunks_exact(2)
+            .all(|pair| pair[0] == pair[1])
+    );
+    hashmap.set(fix(1), fix(1));
+    assert_eq!(hashmap.clear(None), (1, true));
+}
+
+#[test]
+fn test_concurrent_hash_map_compare_and_replace() {
+    use crate::test_helpers::fix;
+
+    let hashmap = ConcurrentHashMap::new();
+    hashmap.set(fix(1), fix(10));
+    assert!(!hashmap.replace_if(fix(1), fix(11), fix(20)));
+    assert!(!hashmap.replace_if(fix(2), fix(10), fix(20)));
+    assert!(hashmap.replace_if(fix(1), fix(10), fix(20)));
+    assert_eq!(hashmap.get(fix(1)), Some(fix(20)));
+
+    assert!(!hashmap.delete_if(fix(1), fix(10)));
+    assert!(hashmap.delete_if(fix(1), fix(20)));
+    assert!(hashmap.is_empty());
+}
+
+#[test]
+fn test_concurrent_hash_map_subscriptions() {
+    let hashmap = ConcurrentHashMap::new();
+    assert!(!hashmap.has_subscribers());
+
+    let queue = MpmcQueue::new(2, 0);
+    let queue_obj = 0x1000;
+    hashmap.subscribe(queue_obj, &queue);
+    assert!(hashmap.has_subscribers());
+
+    assert_eq!(hashmap.publish(0x2000), 0);
+    assert_eq!(hashmap.publish(0x3000), 0);
+    assert_eq!(hashmap.publish(0x4000), 1);
+    assert_eq!(queue.pop(), 0x2000);
+    assert_eq!(queue.pop(), 0x3000);
+
+    assert!(hashmap.unsubscribe(queue_obj));
+    assert!(!hashmap.unsubscribe(queue_obj));
+    assert!(!hashmap.has_subscribers());
+}
+
+#[test]
+fn test_concurrent_hash_map_transaction() {
+    use crate::test_helpers::fix;
+    fn op(kind: u8, key: c_ulong, value: c_ulong) -> ConcurrentHashMapTxOp {
+        ConcurrentHashMapTxOp { kind, key, value }
+    }
+
+    let hashmap = ConcurrentHashMap::with_capacity_and_shards(0, 4);
+    hashmap.set(fix(1), fix(10));
+
+    // move the value of key 1 to key 2
+    let pre_images = hashmap.transaction(&[
+        op(CONCURRENT_HASH_MAP_TX_DELETE, fix(1), 0),
+        op(CONCURRENT_HASH_MAP_TX_SET, fix(2), fix(10)),
+        op(CONCURRENT_HASH_MAP_TX_GET, fix(2), 0),
+        op(CONCURRENT_HASH_MAP_TX_GET, fix(1), 0),
+    ]);
+    assert_eq!(pre_images, [Some(fix(10)), None, Some(fix(10)), None]);
+    assert_eq!(hashmap.get(fix(1)), None);
+    assert_eq!(hashmap.get(fix(2)), Some(fix(10)));
+
+    let hashmap = std::sync::Arc::new(hashmap);
+    let threads = (0..4)
+        .map(|_| {
+            let hashmap = std::sync::Arc::clone(&hashmap);
+            std::thread::spawn(move || {
+                for n in 0..1_000 {
+                    let (from, to) = if n % 2 == 0 { (2, 3) } else { (3, 2) };
+                    let pre_images = hashmap.transaction(&[
+                        op(CONCURRENT_HASH_MAP_TX_DELETE, fix(from), 0),
+                        op(CONCURRENT_HASH_MAP_TX_GET, fix(to), 0),
+                    ]);
+                    if let [Some(value), None] = pre_images[..] {
+                        hashmap.transaction(&[op(CONCURRENT_HASH_MAP_TX_SET, fix(to), value)]);
+                    } else if let [Some(value), _] = pre_images[..] {
+                        hashmap.transaction(&[op(CONCURRENT_HASH_MAP_TX_SET, fix(from), value)]);
+                    }
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert_eq!(hashmap.len(), 1);
+}
+
+#[test]
+fn test_concurrent_hash_map_mark_under_write_lock() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::test_helpers::fix;
+    static MARKED: AtomicUsize = AtomicUsize::new(0);
+    extern "C" fn count(_: c_ulong) {
+        MARKED.fetch_add(1, Ordering::Relaxed);
+    }
+
+    let hashmap = ConcurrentHashMap::with_capacity_and_shards(0, 4);
+    for n in 0..100 {
+        hashmap.set(fix(n), fix(n));
+    }
+    // a mutator that triggered GC from a callback keeps its shard locked
+    let _guards = hashmap
+        .map
+        .shards()
+        .iter()
+        .map(|shard| shard.write())
+        .collect::<Vec<_>>();
+    hashmap.mark(count);
+    assert_eq!(MARKED.load(Ordering::Relaxed), 200);
+}
+
+#[test]
+fn test_concurrent_hash_map_compute() {
+    use std::sync::OnceLock;
+
+    use crate::test_helpers::{fix, unfix};
+    const ABSENT: c_ulong = c_ulong::MAX;
+    static HASHMAP: OnceLock<ConcurrentHashMap> = OnceLock::new();
+
+    // reads another key of the same map, this would deadlock in `upsert`
+    extern "C" fn add_other(value: c_ulong) -> c_ulong {
+        let other = HASHMAP.get().unwrap().get(fix(2)).unwrap();
+        fix(unfix(value) + unfix(other))
+    }
+    extern "C" fn increment(value: c_ulong) -> c_ulong {
+        if value == ABSENT {
+            fix(1)
+        } else {
+            fix(unfix(value) + 1)
+        }
+    }
+
+    let hashmap = HASHMAP.get_or_init(ConcurrentHashMap::new);
+    hashmap.set(fix(1), fix(10));
+    hashmap.set(fix(2), fix(5));
+    assert_eq!(
+        hashmap.compute(fix(1), ABSENT, add_other),
+        (fix(10), fix(15))
+    );
+    assert_eq!(hashmap.get(fix(1)), Some(fix(15)));
+
+    let threads = (0..4)
+        .map(|_| {
+            std::thread::spawn(|| {
+                for _ in 0..1_000 {
+                    HASHMAP.get().unwrap().compute(fix(3), ABSENT, increment);
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert_eq!(hashmap.get(fix(3)), Some(fix(4_000)));
 }
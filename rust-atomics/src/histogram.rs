@@ -0,0 +1,247 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Every power of two is split into 2^SUB_BUCKET_BITS linear sub-buckets,
+// so a recorded value is off by at most 1/32 (~3%) of its magnitude.
+const SUB_BUCKET_BITS: u32 = 5;
+const SUB_BUCKET_COUNT: usize = 1 << SUB_BUCKET_BITS;
+const BUCKETS_COUNT: usize = (65 - SUB_BUCKET_BITS as usize) * SUB_BUCKET_COUNT;
+
+#[derive(Debug)]
+pub struct Histogram {
+    buckets: Box<[AtomicU64]>,
+    count: AtomicU64,
+    sum: AtomicU64,
+    min: AtomicU64,
+    max: AtomicU64,
+}
+
+fn bucket_idx(value: u64) -> usize {
+    let bits = u64::BITS - value.leading_zeros();
+    if bits <= SUB_BUCKET_BITS + 1 {
+        value as usize
+    } else {
+        let shift = bits - SUB_BUCKET_BITS - 1;
+        (shift as usize + 1) * SUB_BUCKET_COUNT + (value >> shift) as usize - SUB_BUCKET_COUNT
+    }
+}
+
+fn bucket_highest_value(idx: usize) -> u64 {
+    if idx < 2 * SUB_BUCKET_COUNT {
+        idx as u64
+    } else {
+        let shift = idx / SUB_BUCKET_COUNT - 1;
+        let mantissa = (idx % SUB_BUCKET_COUNT + SUB_BUCKET_COUNT) as u64;
+        (((mantissa + 1) as u128) << shift).saturating_sub(1) as u64
+    }
+}
+
+impl Histogram {
+    fn alloc() -> Self {
+        Self {
+            buckets: Box::new([]),
+            count: AtomicU64::new(0),
+            sum: AtomicU64::new(0),
+            min: AtomicU64::new(u64::MAX),
+            max: AtomicU64::new(0),
+        }
+    }
+
+    fn init(&mut self) {
+        self.buckets = (0..BUCKETS_COUNT).map(|_| AtomicU64::new(0)).collect();
+        self.reset();
+    }
+
+    pub fn new() -> Self {
+        let mut histogram = Self::alloc();
+        histogram.init();
+        histogram
+    }
+
+    pub fn record(&self, value: u64) {
+        self.buckets[bucket_idx(value)].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum.fetch_add(value, Ordering::Relaxed);
+        self.min.fetch_min(value, Ordering::Relaxed);
+        self.max.fetch_max(value, Ordering::Relaxed);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    pub fn sum(&self) -> u64 {
+        self.sum.load(Ordering::Relaxed)
+    }
+
+    pub fn min(&self) -> u64 {
+        match self.min.load(Ordering::Relaxed) {
+            u64::MAX if self.count() == 0 => 0,
+            min => min,
+        }
+    }
+
+    pub fn max(&self) -> u64 {
+        self.max.load(Ordering::Relaxed)
+    }
+
+    pub fn percentile(&self, percentile: f64) -> u64 {
+        let counts = self
+            .buckets
+            .iter()
+            .map(|bucket| bucket.load(Ordering::Relaxed))
+            .collect::<Vec<_>>();
+        let total = counts.iter().sum::<u64>();
+        if total == 0 {
+            return 0;
+        }
+
+        let percentile = percentile.clamp(0.0, 100.0);
+        let rank = ((percentile / 100.0 * total as f64).ceil() as u64).max(1);
+
+        let mut seen = 0;
+        for (idx, count) in counts.into_iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return bucket_highest_value(idx).min(self.max());
+            }
+        }
+        self.max()
+    }
+
+    pub fn merge(&self, other: &Histogram) {
+        for (idx, bucket) in other.buckets.iter().enumerate() {
+            let count = bucket.load(Ordering::Relaxed);
+            if count != 0 {
+                self.buckets[idx].fetch_add(count, Ordering::Relaxed);
+            }
+        }
+        self.count.fetch_add(other.count(), Ordering::Relaxed);
+        self.sum.fetch_add(other.sum(), Ordering::Relaxed);
+        self.min
+            .fetch_min(other.min.load(Ordering::Relaxed), Ordering::Relaxed);
+        self.max.fetch_max(other.max(), Ordering::Relaxed);
+    }
+
+    pub fn reset(&self) {
+        for bucket in self.buckets.iter() {
+            bucket.store(0, Ordering::Relaxed);
+        }
+        self.count.store(0, Ordering::Relaxed);
+        self.sum.store(0, Ordering::Relaxed);
+        self.min.store(u64::MAX, Ordering::Relaxed);
+        self.max.store(0, Ordering::Relaxed);
+    }
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn histogram_alloc(histogram: *mut Histogram) {
+    unsafe { histogram.write(Histogram::alloc()) }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn histogram_init(histogram: *mut Histogram) {
+    let histogram = unsafe { histogram.as_mut().unwrap() };
+    histogram.init();
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn histogram_drop(histogram: *mut Histogram) {
+    unsafe { std::ptr::drop_in_place(histogram) };
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn histogram_record(histogram: *const Histogram, value: u64) {
+    let histogram = unsafe { histogram.as_ref().unwrap() };
+    histogram.record(value);
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn histogram_count(histogram: *const Histogram) -> u64 {
+    let histogram = unsafe { histogram.as_ref().unwrap() };
+    histogram.count()
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn histogram_sum(histogram: *const Histogram) -> u64 {
+    let histogram = unsafe { histogram.as_ref().unwrap() };
+    histogram.sum()
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn histogram_min(histogram: *const Histogram) -> u64 {
+    let histogram = unsafe { histogram.as_ref().unwrap() };
+    histogram.min()
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn histogram_max(histogram: *const Histogram) -> u64 {
+    let histogram = unsafe { histogram.as_ref().unwrap() };
+    histogram.max()
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn histogram_percentile(histogram: *const Histogram, percentile: f64) -> u64 {
+    let histogram = unsafe { histogram.as_ref().unwrap() };
+    histogram.percentile(percentile)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn histogram_merge(histogram: *const Histogram, other: *const Histogram) {
+    let histogram = unsafe { histogram.as_ref().unwrap() };
+    let other = unsafe { other.as_ref().unwrap() };
+    histogram.merge(other);
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn histogram_reset(histogram: *const Histogram) {
+    let histogram = unsafe { histogram.as_ref().unwrap() };
+    histogram.reset();
+}
+
+pub const HISTOGRAM_SIZE: usize = 48;
+
+#[test]
+fn test_histogram() {
+    assert_eq!(
+        HISTOGRAM_SIZE,
+        std::mem::size_of::<Histogram>(),
+        "size mismatch"
+    );
+    assert!(crate::is_sync_and_send::<Histogram>());
+
+    for value in [0, 1, 63, 64, 65, 1_000, 123_456_789, u64::MAX] {
+        let idx = bucket_idx(value);
+        assert!(idx < BUCKETS_COUNT);
+        assert!(bucket_highest_value(idx) >= value);
+        assert!(idx == 0 || bucket_highest_value(idx - 1) < value);
+    }
+
+    let histogram = Histogram::new();
+    for value in 1..=1_000 {
+        histogram.record(value);
+    }
+    assert_eq!(histogram.count(), 1_000);
+    assert_eq!(histogram.sum(), 500_500);
+    assert_eq!(histogram.min(), 1);
+    assert_eq!(histogram.max(), 1_000);
+    assert!(histogram.percentile(50.0).abs_diff(500) <= 500 / 32);
+    assert!(histogram.percentile(99.0).abs_diff(990) <= 990 / 32);
+    assert_eq!(histogram.percentile(100.0), 1_000);
+
+    let other = Histogram::new();
+    other.record(5_000);
+    histogram.merge(&other);
+    assert_eq!(histogram.count(), 1_001);
+    assert_eq!(histogram.max(), 5_000);
+
+    histogram.reset();
+    assert_eq!(histogram.count(), 0);
+    assert_eq!(histogram.min(), 0);
+    assert_eq!(histogram.percentile(50.0), 0);
+}
@@ -6,9 +6,42 @@ pub use plain_counter::*;
 mod counter;
 pub use counter::*;
 
+mod striped_counter;
+pub use striped_counter::*;
+
+mod atomic_i64_counter;
+pub use atomic_i64_counter::*;
+
+mod atomic_f64;
+pub use atomic_f64::*;
+
+mod atomic_ref;
+pub use atomic_ref::*;
+
+mod histogram;
+pub use histogram::*;
+
+mod metrics_registry;
+pub use metrics_registry::*;
+
 mod hashmap;
 pub use hashmap::*;
 
+mod lru_cache;
+pub use lru_cache::*;
+
+mod ttl_map;
+pub use ttl_map::*;
+
+mod set;
+pub use set::*;
+
+mod counter_map;
+pub use counter_map::*;
+
+mod skip_list_map;
+pub use skip_list_map::*;
+
 mod fixed_size_object_pool;
 pub use fixed_size_object_pool::*;
 
@@ -26,7 +59,42 @@ pub(crate) use gc_guard::GcGuard;
 
 mod sem;
 
+mod rvalue;
+
+#[cfg(feature = "race-detector")]
+mod race_detector;
+#[cfg(feature = "race-detector")]
+pub(crate) use race_detector::RaceDetector;
+
 #[cfg(test)]
+#[expect(clippy::extra_unused_type_parameters)]
 pub(crate) fn is_sync_and_send<T: Sync + Send>() -> bool {
     true
 }
+
+// Ruby is not linked into unit tests, so keys are compared by identity there
+#[cfg(test)]
+#[unsafe(no_mangle)]
+extern "C" fn rb_hash(obj: std::ffi::c_ulong) -> std::ffi::c_ulong {
+    obj
+}
+
+#[cfg(test)]
+#[unsafe(no_mangle)]
+extern "C" fn rb_eql(lhs: std::ffi::c_ulong, rhs: std::ffi::c_ulong) -> std::ffi::c_int {
+    (lhs == rhs) as std::ffi::c_int
+}
+
+#[cfg(test)]
+pub(crate) mod test_helpers {
+    use std::ffi::c_ulong;
+
+    // encodes `n` as a Fixnum VALUE
+    pub(crate) fn fix(n: c_ulong) -> c_ulong {
+        (n << 1) | 1
+    }
+
+    pub(crate) fn unfix(value: c_ulong) -> c_ulong {
+        value >> 1
+    }
+}
@@ -0,0 +1,367 @@
+use crate::hashmap::HashedKey;
+use parking_lot::Mutex;
+use std::{
+    collections::{BTreeMap, HashMap},
+    ffi::c_ulong,
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+};
+
+struct LruEntry {
+    value: c_ulong,
+    tick: u64,
+}
+
+#[derive(Default)]
+struct LruShard {
+    map: HashMap<HashedKey, LruEntry>,
+    // tick of the last access -> key, first entry is the least recently used one
+    order: BTreeMap<u64, HashedKey>,
+}
+
+impl LruShard {
+    fn touch(&mut self, key: HashedKey, tick: u64) -> Option<c_ulong> {
+        let entry = self.map.get_mut(&key)?;
+        self.order.remove(&entry.tick);
+        entry.tick = tick;
+        self.order.insert(tick, key);
+        Some(entry.value)
+    }
+
+    // Returns `true` if `key` is new
+    fn insert(&mut self, key: HashedKey, value: c_ulong, tick: u64) -> bool {
+        if self.touch(key, tick).is_some() {
+            self.map.get_mut(&key).unwrap().value = value;
+            return false;
+        }
+        self.map.insert(key, LruEntry { value, tick });
+        self.order.insert(tick, key);
+        true
+    }
+
+    fn remove(&mut self, key: HashedKey) -> Option<c_ulong> {
+        let entry = self.map.remove(&key)?;
+        self.order.remove(&entry.tick);
+        Some(entry.value)
+    }
+
+    fn oldest_tick(&self) -> Option<u64> {
+        self.order.first_key_value().map(|(tick, _)| *tick)
+    }
+
+    fn pop_lru(&mut self) -> bool {
+        let Some((_, key)) = self.order.pop_first() else {
+            return false;
+        };
+        self.map.remove(&key);
+        true
+    }
+}
+
+// Shards only split locking, capacity and recency are global:
+// every access takes a tick from one clock, and once the cache is full
+// an insert evicts the entry with the oldest tick across all shards.
+pub struct ConcurrentLruCache {
+    shards: Box<[Mutex<LruShard>]>,
+    capacity: usize,
+    len: AtomicUsize,
+    clock: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl ConcurrentLruCache {
+    fn alloc() -> Self {
+        Self {
+            shards: Box::new([]),
+            capacity: 0,
+            len: AtomicUsize::new(0),
+            clock: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    fn init(&mut self, capacity: usize, shards_count: usize) {
+        assert!(capacity >= 1);
+        assert!(shards_count >= 1);
+        let shards_count = shards_count.min(capacity);
+        self.shards = (0..shards_count)
+            .map(|_| Mutex::new(LruShard::default()))
+            .collect();
+        self.capacity = capacity;
+    }
+
+    fn shard(&self, key: &HashedKey) -> &Mutex<LruShard> {
+        &self.shards[key.hash as usize % self.shards.len()]
+    }
+
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn get(&self, key: c_ulong) -> Option<c_ulong> {
+        let key = HashedKey::new(key);
+        let value = self.shard(&key).lock().touch(key, self.tick());
+        let stat = if value.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        stat.fetch_add(1, Ordering::Relaxed);
+        value
+    }
+
+    fn set(&self, key: c_ulong, value: c_ulong) {
+        let key = HashedKey::new(key);
+        // `len` is only updated under the lock of the changed shard,
+        // so it can't be decremented for an entry before it's counted
+        let is_full = {
+            let mut shard = self.shard(&key).lock();
+            shard.insert(key, value, self.tick())
+                && self.len.fetch_add(1, Ordering::Relaxed) >= self.capacity
+        };
+        if is_full {
+            self.evict_lru();
+        }
+    }
+
+    // Only one shard is locked at a time, so concurrent evictions can't deadlock
+    fn evict_lru(&self) {
+        loop {
+            let oldest = self
+                .shards
+                .iter()
+                .filter_map(|shard| Some((shard.lock().oldest_tick()?, shard)))
+                .min_by_key(|(tick, _)| *tick);
+            let Some((_, shard)) = oldest else {
+                return;
+            };
+            // the shard may have been drained since it was scanned
+            let mut shard = shard.lock();
+            if shard.pop_lru() {
+                self.len.fetch_sub(1, Ordering::Relaxed);
+                return;
+            }
+        }
+    }
+
+    fn delete(&self, key: c_ulong) -> Option<c_ulong> {
+        let key = HashedKey::new(key);
+        let mut shard = self.shard(&key).lock();
+        let value = shard.remove(key)?;
+        self.len.fetch_sub(1, Ordering::Relaxed);
+        Some(value)
+    }
+
+    fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    fn clear(&self) {
+        for shard in self.shards.iter() {
+            let mut shard = shard.lock();
+            self.len.fetch_sub(shard.map.len(), Ordering::Relaxed);
+            shard.map.clear();
+            shard.order.clear();
+        }
+    }
+
+    fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    // Lookups call `rb_eql` with the shard locked, so GC may run on the thread
+    // that holds the lock. Like `mark_shards`, a locked shard is read without
+    // the lock: its holder is parked in Ruby while the shard is consistent.
+    fn mark(&self, f: extern "C" fn(c_ulong)) {
+        for shard in self.shards.iter() {
+            let guard = shard.try_lock();
+            let shard = match &guard {
+                Some(shard) => &**shard,
+                None => unsafe { &*shard.data_ptr() },
+            };
+            for (key, entry) in shard.map.iter() {
+                f(key.key.0);
+                f(entry.value);
+            }
+        }
+    }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_lru_cache_alloc(cache: *mut ConcurrentLruCache) {
+    unsafe { cache.write(ConcurrentLruCache::alloc()) }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_lru_cache_init(
+    cache: *mut ConcurrentLruCache,
+    capacity: usize,
+    shards_count: usize,
+) {
+    let cache = unsafe { cache.as_mut().unwrap() };
+    cache.init(capacity, shards_count);
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_lru_cache_drop(cache: *mut ConcurrentLruCache) {
+    unsafe { std::ptr::drop_in_place(cache) };
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_lru_cache_mark(
+    cache: *const ConcurrentLruCache,
+    f: extern "C" fn(c_ulong),
+) {
+    let cache = unsafe { cache.as_ref().unwrap() };
+    cache.mark(f);
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_lru_cache_get(
+    cache: *const ConcurrentLruCache,
+    key: c_ulong,
+    fallback: c_ulong,
+) -> c_ulong {
+    let cache = unsafe { cache.as_ref().unwrap() };
+    cache.get(key).unwrap_or(fallback)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_lru_cache_set(
+    cache: *const ConcurrentLruCache,
+    key: c_ulong,
+    value: c_ulong,
+) {
+    let cache = unsafe { cache.as_ref().unwrap() };
+    cache.set(key, value);
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_lru_cache_delete(
+    cache: *const ConcurrentLruCache,
+    key: c_ulong,
+    fallback: c_ulong,
+) -> c_ulong {
+    let cache = unsafe { cache.as_ref().unwrap() };
+    cache.delete(key).unwrap_or(fallback)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_lru_cache_size(cache: *const ConcurrentLruCache) -> usize {
+    let cache = unsafe { cache.as_ref().unwrap() };
+    cache.len()
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_lru_cache_clear(cache: *const ConcurrentLruCache) {
+    let cache = unsafe { cache.as_ref().unwrap() };
+    cache.clear();
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_lru_cache_hits(cache: *const ConcurrentLruCache) -> u64 {
+    let cache = unsafe { cache.as_ref().unwrap() };
+    cache.hits()
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_lru_cache_misses(cache: *const ConcurrentLruCache) -> u64 {
+    let cache = unsafe { cache.as_ref().unwrap() };
+    cache.misses()
+}
+
+pub const CONCURRENT_LRU_CACHE_SIZE: usize = 56;
+
+#[test]
+fn test_concurrent_lru_cache() {
+    assert_eq!(
+        CONCURRENT_LRU_CACHE_SIZE,
+        std::mem::size_of::<ConcurrentLruCache>(),
+        "size mismatch"
+    );
+    assert!(crate::is_sync_and_send::<ConcurrentLruCache>());
+}
+
+#[test]
+fn test_concurrent_lru_cache_eviction() {
+    use crate::test_helpers::fix;
+
+    let mut cache = ConcurrentLruCache::alloc();
+    cache.init(2, 1);
+    cache.set(fix(1), fix(10));
+    cache.set(fix(2), fix(20));
+    assert_eq!(cache.get(fix(1)), Some(fix(10)));
+    cache.set(fix(3), fix(30));
+    assert_eq!(cache.get(fix(2)), None);
+    assert_eq!(cache.get(fix(1)), Some(fix(10)));
+    assert_eq!(cache.get(fix(3)), Some(fix(30)));
+    assert_eq!(cache.len(), 2);
+    assert_eq!((cache.hits(), cache.misses()), (3, 1));
+}
+
+#[test]
+fn test_concurrent_lru_cache_global_capacity() {
+    use crate::test_helpers::fix;
+
+    let mut cache = ConcurrentLruCache::alloc();
+    cache.init(10, 4);
+    for n in 0..100 {
+        cache.set(fix(n), fix(n));
+        assert!(cache.len() <= 10);
+    }
+    assert_eq!(cache.len(), 10);
+    // exactly the 10 most recently used keys survive, wherever they are hashed to
+    for n in 90..100 {
+        assert_eq!(cache.get(fix(n)), Some(fix(n)));
+    }
+    assert_eq!(cache.get(fix(89)), None);
+
+    let cache = std::sync::Arc::new(cache);
+    let threads = (0..4)
+        .map(|thread| {
+            let cache = std::sync::Arc::clone(&cache);
+            std::thread::spawn(move || {
+                for n in 0..1_000 {
+                    cache.set(fix(thread * 1_000 + n), fix(n));
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert_eq!(cache.len(), 10);
+    let stored = cache
+        .shards
+        .iter()
+        .map(|shard| shard.lock().map.len())
+        .sum::<usize>();
+    assert_eq!(stored, 10);
+}
+
+#[test]
+fn test_concurrent_lru_cache_mark_under_lock() {
+    use crate::test_helpers::fix;
+    static MARKED: AtomicUsize = AtomicUsize::new(0);
+    extern "C" fn count(_: c_ulong) {
+        MARKED.fetch_add(1, Ordering::Relaxed);
+    }
+
+    let mut cache = ConcurrentLruCache::alloc();
+    cache.init(100, 4);
+    for n in 0..100 {
+        cache.set(fix(n), fix(n));
+    }
+    let _guards = cache
+        .shards
+        .iter()
+        .map(|shard| shard.lock())
+        .collect::<Vec<_>>();
+    cache.mark(count);
+    assert_eq!(MARKED.load(Ordering::Relaxed), 200);
+}
@@ -0,0 +1,342 @@
+use crate::{AtomicCounter, AtomicF64, CounterOverflow, Histogram};
+use dashmap::mapref::entry::Entry;
+use std::fmt::Write;
+
+enum MetricValue {
+    Counter(AtomicCounter),
+    Gauge(AtomicF64),
+    Histogram(Histogram),
+}
+
+struct Metric {
+    help: String,
+    value: MetricValue,
+}
+
+impl MetricValue {
+    fn same_kind(&self, other: &MetricValue) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+pub struct MetricsRegistry {
+    metrics: dashmap::DashMap<String, Metric>,
+}
+
+const SUMMARY_QUANTILES: [f64; 3] = [0.5, 0.9, 0.99];
+
+fn is_valid_metric_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' || c == ':' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+}
+
+fn format_float(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value == f64::INFINITY {
+        "+Inf".to_string()
+    } else if value == f64::NEG_INFINITY {
+        "-Inf".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+impl MetricsRegistry {
+    fn new() -> Self {
+        Self {
+            metrics: dashmap::DashMap::new(),
+        }
+    }
+
+    fn register(&self, name: &str, help: &str, value: MetricValue) -> bool {
+        if !is_valid_metric_name(name) {
+            return false;
+        }
+        match self.metrics.entry(name.to_string()) {
+            Entry::Occupied(entry) => entry.get().value.same_kind(&value),
+            Entry::Vacant(entry) => {
+                entry.insert(Metric {
+                    help: help.to_string(),
+                    value,
+                });
+                true
+            }
+        }
+    }
+
+    fn register_counter(&self, name: &str, help: &str) -> bool {
+        let counter = AtomicCounter::new(0, CounterOverflow::Wrapping);
+        self.register(name, help, MetricValue::Counter(counter))
+    }
+
+    fn register_gauge(&self, name: &str, help: &str) -> bool {
+        self.register(name, help, MetricValue::Gauge(AtomicF64::new(0.0)))
+    }
+
+    fn register_histogram(&self, name: &str, help: &str) -> bool {
+        self.register(name, help, MetricValue::Histogram(Histogram::new()))
+    }
+
+    fn counter_add(&self, name: &str, delta: u64) -> bool {
+        match self.metrics.get(name).as_deref() {
+            Some(Metric {
+                value: MetricValue::Counter(counter),
+                ..
+            }) => {
+                let _ = counter.add(delta);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn gauge_set(&self, name: &str, value: f64) -> bool {
+        match self.metrics.get(name).as_deref() {
+            Some(Metric {
+                value: MetricValue::Gauge(gauge),
+                ..
+            }) => {
+                gauge.swap(value);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn gauge_add(&self, name: &str, delta: f64) -> bool {
+        match self.metrics.get(name).as_deref() {
+            Some(Metric {
+                value: MetricValue::Gauge(gauge),
+                ..
+            }) => {
+                gauge.add(delta);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn histogram_record(&self, name: &str, value: u64) -> bool {
+        match self.metrics.get(name).as_deref() {
+            Some(Metric {
+                value: MetricValue::Histogram(histogram),
+                ..
+            }) => {
+                histogram.record(value);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut names = self
+            .metrics
+            .iter()
+            .map(|pair| pair.key().clone())
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+
+        let mut out = String::new();
+        for name in names {
+            let Some(metric) = self.metrics.get(&name) else {
+                continue;
+            };
+            if !metric.help.is_empty() {
+                let help = metric.help.replace('\\', "\\\\").replace('\n', "\\n");
+                writeln!(out, "# HELP {name} {help}").unwrap();
+            }
+            match &metric.value {
+                MetricValue::Counter(counter) => {
+                    writeln!(out, "# TYPE {name} counter").unwrap();
+                    writeln!(out, "{name} {}", counter.read()).unwrap();
+                }
+                MetricValue::Gauge(gauge) => {
+                    writeln!(out, "# TYPE {name} gauge").unwrap();
+                    writeln!(out, "{name} {}", format_float(gauge.read())).unwrap();
+                }
+                MetricValue::Histogram(histogram) => {
+                    writeln!(out, "# TYPE {name} summary").unwrap();
+                    for quantile in SUMMARY_QUANTILES {
+                        let value = histogram.percentile(quantile * 100.0);
+                        writeln!(out, "{name}{{quantile=\"{quantile}\"}} {value}").unwrap();
+                    }
+                    writeln!(out, "{name}_sum {}", histogram.sum()).unwrap();
+                    writeln!(out, "{name}_count {}", histogram.count()).unwrap();
+                }
+            }
+        }
+        out
+    }
+}
+
+unsafe fn str_from_raw<'a>(ptr: *const u8, len: usize) -> Option<&'a str> {
+    let bytes = unsafe { std::slice::from_raw_parts(ptr, len) };
+    std::str::from_utf8(bytes).ok()
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn metrics_registry_init(registry: *mut MetricsRegistry) {
+    unsafe { registry.write(MetricsRegistry::new()) }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn metrics_registry_drop(registry: *mut MetricsRegistry) {
+    unsafe { std::ptr::drop_in_place(registry) };
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn metrics_registry_register_counter(
+    registry: *const MetricsRegistry,
+    name: *const u8,
+    name_len: usize,
+    help: *const u8,
+    help_len: usize,
+) -> bool {
+    let registry = unsafe { registry.as_ref().unwrap() };
+    match unsafe { (str_from_raw(name, name_len), str_from_raw(help, help_len)) } {
+        (Some(name), Some(help)) => registry.register_counter(name, help),
+        _ => false,
+    }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn metrics_registry_register_gauge(
+    registry: *const MetricsRegistry,
+    name: *const u8,
+    name_len: usize,
+    help: *const u8,
+    help_len: usize,
+) -> bool {
+    let registry = unsafe { registry.as_ref().unwrap() };
+    match unsafe { (str_from_raw(name, name_len), str_from_raw(help, help_len)) } {
+        (Some(name), Some(help)) => registry.register_gauge(name, help),
+        _ => false,
+    }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn metrics_registry_register_histogram(
+    registry: *const MetricsRegistry,
+    name: *const u8,
+    name_len: usize,
+    help: *const u8,
+    help_len: usize,
+) -> bool {
+    let registry = unsafe { registry.as_ref().unwrap() };
+    match unsafe { (str_from_raw(name, name_len), str_from_raw(help, help_len)) } {
+        (Some(name), Some(help)) => registry.register_histogram(name, help),
+        _ => false,
+    }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn metrics_registry_counter_add(
+    registry: *const MetricsRegistry,
+    name: *const u8,
+    name_len: usize,
+    delta: u64,
+) -> bool {
+    let registry = unsafe { registry.as_ref().unwrap() };
+    unsafe { str_from_raw(name, name_len) }.is_some_and(|name| registry.counter_add(name, delta))
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn metrics_registry_gauge_set(
+    registry: *const MetricsRegistry,
+    name: *const u8,
+    name_len: usize,
+    value: f64,
+) -> bool {
+    let registry = unsafe { registry.as_ref().unwrap() };
+    unsafe { str_from_raw(name, name_len) }.is_some_and(|name| registry.gauge_set(name, value))
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn metrics_registry_gauge_add(
+    registry: *const MetricsRegistry,
+    name: *const u8,
+    name_len: usize,
+    delta: f64,
+) -> bool {
+    let registry = unsafe { registry.as_ref().unwrap() };
+    unsafe { str_from_raw(name, name_len) }.is_some_and(|name| registry.gauge_add(name, delta))
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn metrics_registry_histogram_record(
+    registry: *const MetricsRegistry,
+    name: *const u8,
+    name_len: usize,
+    value: u64,
+) -> bool {
+    let registry = unsafe { registry.as_ref().unwrap() };
+    unsafe { str_from_raw(name, name_len) }
+        .is_some_and(|name| registry.histogram_record(name, value))
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn metrics_registry_render(
+    registry: *const MetricsRegistry,
+    buf: *mut u8,
+    cap: usize,
+) -> usize {
+    let registry = unsafe { registry.as_ref().unwrap() };
+    let out = registry.render();
+    if out.len() <= cap {
+        unsafe { std::ptr::copy_nonoverlapping(out.as_ptr(), buf, out.len()) };
+    }
+    out.len()
+}
+
+pub const METRICS_REGISTRY_SIZE: usize = 40;
+
+#[test]
+fn test_metrics_registry() {
+    assert_eq!(
+        METRICS_REGISTRY_SIZE,
+        std::mem::size_of::<MetricsRegistry>(),
+        "size mismatch"
+    );
+    assert!(crate::is_sync_and_send::<MetricsRegistry>());
+
+    let registry = MetricsRegistry::new();
+    assert!(registry.register_counter("http_requests_total", "Total requests"));
+    assert!(registry.register_counter("http_requests_total", "Total requests"));
+    assert!(!registry.register_gauge("http_requests_total", ""));
+    assert!(!registry.register_gauge("0invalid", ""));
+    assert!(registry.register_gauge("queue_depth", ""));
+    assert!(registry.register_histogram("latency_us", "Latency\nin us"));
+
+    assert!(registry.counter_add("http_requests_total", 3));
+    assert!(!registry.counter_add("queue_depth", 3));
+    assert!(!registry.counter_add("missing", 3));
+    assert!(registry.gauge_set("queue_depth", 2.5));
+    assert!(registry.gauge_add("queue_depth", -1.0));
+    assert!(registry.histogram_record("latency_us", 10));
+
+    assert_eq!(
+        registry.render(),
+        [
+            "# HELP http_requests_total Total requests",
+            "# TYPE http_requests_total counter",
+            "http_requests_total 3",
+            "# HELP latency_us Latency\\nin us",
+            "# TYPE latency_us summary",
+            "latency_us{quantile=\"0.5\"} 10",
+            "latency_us{quantile=\"0.9\"} 10",
+            "latency_us{quantile=\"0.99\"} 10",
+            "latency_us_sum 10",
+            "latency_us_count 1",
+            "# TYPE queue_depth gauge",
+            "queue_depth 1.5",
+            "",
+        ]
+        .join("\n")
+    );
+}
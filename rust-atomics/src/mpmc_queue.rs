@@ -66,7 +66,7 @@ impl MpmcQueue {
         q
     }
 
-    fn try_push(&self, data: c_ulong) -> bool {
+    pub(crate) fn try_push(&self, data: c_ulong) -> bool {
         let mut cell;
         let mut pos = self.enqueue_pos.load(Ordering::Relaxed);
         loop {
@@ -206,14 +206,14 @@ pub unsafe extern "C" fn mpmc_queue_push(
     let push_payload = unsafe { push_paylod.cast::<MpmcQueuePushPayload>().as_ref().unwrap() };
     let q = unsafe { push_payload.queue.as_ref().unwrap() };
     q.push(push_payload.item);
-    return std::ptr::null_mut();
+    std::ptr::null_mut()
 }
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn mpmc_queue_pop(q: *mut std::ffi::c_void) -> *mut std::ffi::c_void {
     let q = unsafe { q.cast::<MpmcQueue>().as_ref().unwrap() };
     let item = q.pop();
-    unsafe { std::mem::transmute(item) }
+    std::ptr::with_exposed_provenance_mut(item as usize)
 }
 
 pub const MPMC_QUEUE_OBJECT_SIZE: usize = 80;
@@ -1,15 +1,28 @@
+#[cfg(feature = "race-detector")]
+use crate::RaceDetector;
+
 #[derive(Debug)]
 pub struct PlainCounter {
     value: u64,
+    #[cfg(feature = "race-detector")]
+    detector: RaceDetector,
 }
 
 impl PlainCounter {
     pub fn new(n: u64) -> Self {
-        Self { value: n }
+        Self {
+            value: n,
+            #[cfg(feature = "race-detector")]
+            detector: RaceDetector::new(),
+        }
     }
 
     pub fn inc(&mut self) {
+        #[cfg(feature = "race-detector")]
+        self.detector.enter();
         self.value += 1;
+        #[cfg(feature = "race-detector")]
+        self.detector.exit();
     }
 
     pub fn read(&self) -> u64 {
@@ -34,7 +47,24 @@ pub unsafe extern "C" fn plain_counter_read(counter: *const PlainCounter) -> u64
     counter.read()
 }
 
+#[cfg(feature = "race-detector")]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn plain_counter_races(counter: *const PlainCounter) -> u64 {
+    let counter = unsafe { counter.as_ref().unwrap() };
+    counter.detector.races()
+}
+
+#[cfg(feature = "race-detector")]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn plain_counter_last_writer(counter: *const PlainCounter) -> u64 {
+    let counter = unsafe { counter.as_ref().unwrap() };
+    counter.detector.last_writer()
+}
+
+#[cfg(not(feature = "race-detector"))]
 pub const PLAIN_COUNTER_SIZE: usize = 8;
+#[cfg(feature = "race-detector")]
+pub const PLAIN_COUNTER_SIZE: usize = 24;
 
 #[test]
 fn test_plain_counter() {
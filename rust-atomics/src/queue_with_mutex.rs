@@ -60,19 +60,19 @@ impl QueueWithMutex {
     }
 
     fn try_push(&self, value: c_ulong) -> bool {
-        if let Some(mut inner) = self.inner.try_lock() {
-            if inner.try_push(value) {
-                return true;
-            }
+        if let Some(mut inner) = self.inner.try_lock()
+            && inner.try_push(value)
+        {
+            return true;
         }
         false
     }
 
     fn try_pop(&self) -> Option<c_ulong> {
-        if let Some(mut inner) = self.inner.try_lock() {
-            if let Some(value) = inner.try_pop() {
-                return Some(value);
-            }
+        if let Some(mut inner) = self.inner.try_lock()
+            && let Some(value) = inner.try_pop()
+        {
+            return Some(value);
         }
 
         None
@@ -0,0 +1,91 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// `state` keeps the id of the last writer in the upper half and the number of
+// writes in flight in the lower half, so that both are updated together
+#[derive(Debug)]
+pub(crate) struct RaceDetector {
+    state: AtomicU64,
+    races: AtomicU64,
+}
+
+const WRITER_SHIFT: u32 = 32;
+const IN_FLIGHT_MASK: u64 = (1 << WRITER_SHIFT) - 1;
+
+static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    static THREAD_ID: u64 = NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed) & IN_FLIGHT_MASK;
+}
+
+impl RaceDetector {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: AtomicU64::new(0),
+            races: AtomicU64::new(0),
+        }
+    }
+
+    // A write is not synchronized with the previous one if it starts while
+    // the previous writer is still in flight; nested writes of the same
+    // thread are not races.
+    pub(crate) fn enter(&self) {
+        let me = THREAD_ID.with(|id| *id);
+        let previous = self
+            .state
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |state| {
+                Some((me << WRITER_SHIFT) | ((state & IN_FLIGHT_MASK) + 1))
+            })
+            .unwrap();
+        let last_writer = previous >> WRITER_SHIFT;
+        if previous & IN_FLIGHT_MASK != 0 && last_writer != me {
+            self.races.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn exit(&self) {
+        self.state.fetch_sub(1, Ordering::AcqRel);
+    }
+
+    pub(crate) fn races(&self) -> u64 {
+        self.races.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn last_writer(&self) -> u64 {
+        self.state.load(Ordering::Acquire) >> WRITER_SHIFT
+    }
+}
+
+#[test]
+fn test_race_detector() {
+    let detector = RaceDetector::new();
+    detector.enter();
+    detector.exit();
+    assert_eq!(detector.races(), 0);
+    assert_eq!(detector.last_writer(), THREAD_ID.with(|id| *id));
+
+    detector.enter();
+    detector.enter();
+    detector.exit();
+    detector.exit();
+    assert_eq!(detector.races(), 0, "nested writes of one thread");
+
+    detector.enter();
+    let other_writer = std::thread::scope(|scope| {
+        scope
+            .spawn(|| {
+                detector.enter();
+                detector.exit();
+                THREAD_ID.with(|id| *id)
+            })
+            .join()
+            .unwrap()
+    });
+    detector.exit();
+    assert_eq!(detector.races(), 1);
+    assert_eq!(detector.last_writer(), other_writer);
+
+    // a later write that doesn't overlap is synchronized
+    detector.enter();
+    detector.exit();
+    assert_eq!(detector.races(), 1);
+}
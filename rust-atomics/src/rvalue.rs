@@ -0,0 +1,203 @@
+use std::{
+    ffi::{c_long, c_ulong},
+    hash::{Hash, Hasher},
+};
+
+// Object layout of MRI >= 3.2 (64-bit, flonums enabled),
+// see include/ruby/internal/{special_consts,value_type,fl_type}.h and core/rstring.h
+const IMMEDIATE_MASK: c_ulong = 0x07;
+const T_MASK: c_ulong = 0x1f;
+const T_STRING: c_ulong = 0x05;
+const FL_FREEZE: c_ulong = 1 << 11;
+const RSTRING_NOEMBED: c_ulong = 1 << 13;
+const ENCODING_SHIFT: c_ulong = 22;
+const ENCODING_MASK: c_ulong = 0x7f << ENCODING_SHIFT;
+const ENCODING_INLINE_MAX: c_ulong = 0x7f;
+// 0.0 is the only flonum that is eql? to a heap object: -0.0 can't be a flonum
+const FLONUM_ZERO: c_ulong = 0x8000_0000_0000_0002;
+
+#[repr(C)]
+struct RString {
+    flags: c_ulong,
+    _klass: c_ulong,
+    len: c_long,
+    // `as.heap.ptr` or the first bytes of `as.embed.ary`
+    ptr: *const u8,
+}
+
+enum RValue<'a> {
+    SpecialConst(c_ulong),
+    String {
+        bytes: &'a [u8],
+        frozen: bool,
+        encoding: c_ulong,
+    },
+    Object,
+}
+
+// Qfalse is 0 and Qnil is either 0x04 or 0x08 (before 3.3),
+// heap pointers are always 8-byte aligned and non-zero.
+fn is_special_const(value: c_ulong) -> bool {
+    value & IMMEDIATE_MASK != 0 || value <= 0x08
+}
+
+unsafe fn classify<'a>(value: c_ulong) -> RValue<'a> {
+    if is_special_const(value) {
+        return RValue::SpecialConst(value);
+    }
+
+    let rstring = value as *const RString;
+    let flags = unsafe { (*rstring).flags };
+    if flags & T_MASK != T_STRING {
+        return RValue::Object;
+    }
+
+    let len = unsafe { (*rstring).len } as usize;
+    let ptr = if flags & RSTRING_NOEMBED != 0 {
+        unsafe { (*rstring).ptr }
+    } else {
+        unsafe { std::ptr::addr_of!((*rstring).ptr).cast::<u8>() }
+    };
+    RValue::String {
+        bytes: unsafe { std::slice::from_raw_parts(ptr, len) },
+        frozen: flags & FL_FREEZE != 0,
+        encoding: (flags & ENCODING_MASK) >> ENCODING_SHIFT,
+    }
+}
+
+/// Hashes immediates and Strings without calling into Ruby.
+/// Returns `false` if `value` must be hashed with `rb_hash`.
+pub(crate) unsafe fn hash_natively<H: Hasher>(value: c_ulong, state: &mut H) -> bool {
+    match unsafe { classify(value) } {
+        // must hash the same as -0.0, which only Ruby can do
+        RValue::SpecialConst(FLONUM_ZERO) => false,
+        RValue::SpecialConst(value) => {
+            value.hash(state);
+            true
+        }
+        RValue::String { bytes, .. } => {
+            bytes.hash(state);
+            true
+        }
+        RValue::Object => false,
+    }
+}
+
+/// Compares immediates and frozen Strings without calling into Ruby.
+/// Returns `None` if `rb_eql` must be used.
+pub(crate) unsafe fn eql_natively(lhs: c_ulong, rhs: c_ulong) -> Option<bool> {
+    if lhs == rhs {
+        return Some(true);
+    }
+    match unsafe { (classify(lhs), classify(rhs)) } {
+        (RValue::SpecialConst(FLONUM_ZERO), RValue::Object)
+        | (RValue::Object, RValue::SpecialConst(FLONUM_ZERO)) => None,
+        // other immediates are never eql? to anything except themselves
+        (RValue::SpecialConst(_), _) | (_, RValue::SpecialConst(_)) => Some(false),
+        (
+            RValue::String {
+                bytes: lhs_bytes,
+                frozen: lhs_frozen,
+                encoding: lhs_encoding,
+            },
+            RValue::String {
+                bytes: rhs_bytes,
+                frozen: rhs_frozen,
+                encoding: rhs_encoding,
+            },
+        ) => {
+            if lhs_bytes != rhs_bytes {
+                Some(false)
+            } else if lhs_frozen
+                && rhs_frozen
+                && lhs_encoding == rhs_encoding
+                && lhs_encoding != ENCODING_INLINE_MAX
+            {
+                Some(true)
+            } else {
+                // same bytes, but encodings may still be incompatible
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+#[test]
+fn test_rvalue() {
+    #[repr(C)]
+    struct EmbedRString {
+        flags: c_ulong,
+        klass: c_ulong,
+        len: c_long,
+        ary: [u8; 8],
+    }
+
+    fn string(s: &str, frozen: bool, encoding: c_ulong) -> Box<EmbedRString> {
+        let mut ary = [0; 8];
+        ary[..s.len()].copy_from_slice(s.as_bytes());
+        Box::new(EmbedRString {
+            flags: T_STRING | if frozen { FL_FREEZE } else { 0 } | (encoding << ENCODING_SHIFT),
+            klass: 0,
+            len: s.len() as c_long,
+            ary,
+        })
+    }
+    fn addr(s: &EmbedRString) -> c_ulong {
+        s as *const EmbedRString as c_ulong
+    }
+    use crate::test_helpers::fix;
+    fn hash(value: c_ulong) -> Option<u64> {
+        let mut hasher = std::hash::DefaultHasher::new();
+        unsafe { hash_natively(value, &mut hasher) }.then(|| hasher.finish())
+    }
+
+    #[repr(C)]
+    struct RFloat {
+        flags: c_ulong,
+        klass: c_ulong,
+        value: f64,
+    }
+    const T_FLOAT: c_ulong = 0x04;
+    let negative_zero = Box::new(RFloat {
+        flags: T_FLOAT,
+        klass: 0,
+        value: -0.0,
+    });
+    let negative_zero = &*negative_zero as *const RFloat as c_ulong;
+    // 1.5 as a flonum
+    let flonum = (1.5_f64.to_bits().rotate_left(3) & !0x01) | 0x02;
+
+    let utf8_abc = string("abc", true, 1);
+    let utf8_abc2 = string("abc", true, 1);
+    let unfrozen_abc = string("abc", false, 1);
+    let binary_abc = string("abc", true, 0);
+    let utf8_abd = string("abd", true, 1);
+
+    unsafe {
+        assert_eq!(eql_natively(fix(1), fix(1)), Some(true));
+        assert_eq!(eql_natively(fix(1), fix(2)), Some(false));
+        assert_eq!(eql_natively(fix(1), addr(&utf8_abc)), Some(false));
+        assert_eq!(eql_natively(0x08, 0x14), Some(false));
+
+        // 0.0.eql?(-0.0) is true, and so is 1.5.eql?(1.5)
+        assert_eq!(eql_natively(FLONUM_ZERO, negative_zero), None);
+        assert_eq!(eql_natively(negative_zero, FLONUM_ZERO), None);
+        assert_eq!(eql_natively(flonum, flonum), Some(true));
+        assert_eq!(eql_natively(flonum, FLONUM_ZERO), Some(false));
+        assert_eq!(eql_natively(FLONUM_ZERO, addr(&utf8_abc)), Some(false));
+
+        assert_eq!(eql_natively(addr(&utf8_abc), addr(&utf8_abc2)), Some(true));
+        assert_eq!(eql_natively(addr(&utf8_abc), addr(&utf8_abd)), Some(false));
+        assert_eq!(eql_natively(addr(&utf8_abc), addr(&unfrozen_abc)), None);
+        assert_eq!(eql_natively(addr(&utf8_abc), addr(&binary_abc)), None);
+    }
+
+    assert_eq!(hash(addr(&utf8_abc)), hash(addr(&unfrozen_abc)));
+    assert_eq!(hash(addr(&utf8_abc)), hash(addr(&binary_abc)));
+    assert_ne!(hash(addr(&utf8_abc)), hash(addr(&utf8_abd)));
+    assert_eq!(hash(fix(42)), hash(fix(42)));
+    assert_eq!(hash(FLONUM_ZERO), None);
+    assert_eq!(hash(negative_zero), None);
+    assert!(hash(flonum).is_some());
+}
@@ -0,0 +1,160 @@
+use crate::hashmap::{HashedKey, mark_shards};
+use std::ffi::c_ulong;
+
+pub struct ConcurrentSet {
+    set: dashmap::DashMap<HashedKey, ()>,
+}
+
+impl ConcurrentSet {
+    fn new() -> Self {
+        Self {
+            set: dashmap::DashMap::new(),
+        }
+    }
+
+    fn add(&self, key: c_ulong) -> bool {
+        self.set.insert(HashedKey::new(key), ()).is_none()
+    }
+
+    fn delete(&self, key: c_ulong) -> bool {
+        self.set.remove(&HashedKey::new(key)).is_some()
+    }
+
+    fn contains(&self, key: c_ulong) -> bool {
+        self.set.contains_key(&HashedKey::new(key))
+    }
+
+    fn len(&self) -> usize {
+        self.set.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.set.is_empty()
+    }
+
+    fn clear(&self) {
+        self.set.clear()
+    }
+
+    fn snapshot(&self) -> Vec<c_ulong> {
+        self.set.iter().map(|pair| pair.key().key.0).collect()
+    }
+
+    fn mark(&self, f: extern "C" fn(c_ulong)) {
+        mark_shards(&self.set, |key, ()| f(key.key.0));
+    }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_set_init(set: *mut ConcurrentSet) {
+    unsafe { set.write(ConcurrentSet::new()) }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_set_drop(set: *mut ConcurrentSet) {
+    unsafe { std::ptr::drop_in_place(set) };
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_set_mark(set: *const ConcurrentSet, f: extern "C" fn(c_ulong)) {
+    let set = unsafe { set.as_ref().unwrap() };
+    set.mark(f);
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_set_add(set: *const ConcurrentSet, key: c_ulong) -> bool {
+    let set = unsafe { set.as_ref().unwrap() };
+    set.add(key)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_set_delete(set: *const ConcurrentSet, key: c_ulong) -> bool {
+    let set = unsafe { set.as_ref().unwrap() };
+    set.delete(key)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_set_contains(set: *const ConcurrentSet, key: c_ulong) -> bool {
+    let set = unsafe { set.as_ref().unwrap() };
+    set.contains(key)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_set_size(set: *const ConcurrentSet) -> usize {
+    let set = unsafe { set.as_ref().unwrap() };
+    set.len()
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_set_is_empty(set: *const ConcurrentSet) -> bool {
+    let set = unsafe { set.as_ref().unwrap() };
+    set.is_empty()
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_set_clear(set: *const ConcurrentSet) {
+    let set = unsafe { set.as_ref().unwrap() };
+    set.clear();
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_set_snapshot(
+    set: *const ConcurrentSet,
+    keys: *mut c_ulong,
+    cap: usize,
+) -> usize {
+    let set = unsafe { set.as_ref().unwrap() };
+    let keys_snapshot = set.snapshot();
+    if keys_snapshot.len() <= cap {
+        unsafe { std::ptr::copy_nonoverlapping(keys_snapshot.as_ptr(), keys, keys_snapshot.len()) };
+    }
+    keys_snapshot.len()
+}
+
+pub const CONCURRENT_SET_SIZE: usize = 40;
+
+#[test]
+fn test_concurrent_set() {
+    assert_eq!(
+        CONCURRENT_SET_SIZE,
+        std::mem::size_of::<ConcurrentSet>(),
+        "size mismatch"
+    );
+    assert!(crate::is_sync_and_send::<ConcurrentSet>());
+
+    use crate::test_helpers::fix;
+
+    let set = ConcurrentSet::new();
+    assert!(set.add(fix(1)));
+    assert!(!set.add(fix(1)));
+    assert!(set.add(fix(2)));
+    assert!(set.contains(fix(2)));
+    assert_eq!(set.len(), 2);
+    assert!(set.delete(fix(2)));
+    assert!(!set.delete(fix(2)));
+    assert_eq!(set.snapshot(), [fix(1)]);
+}
+
+#[test]
+fn test_concurrent_set_mark_under_write_lock() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::test_helpers::fix;
+    static MARKED: AtomicUsize = AtomicUsize::new(0);
+    extern "C" fn count(_: c_ulong) {
+        MARKED.fetch_add(1, Ordering::Relaxed);
+    }
+
+    let set = ConcurrentSet::new();
+    for n in 0..100 {
+        set.add(fix(n));
+    }
+    let _guards = set
+        .set
+        .shards()
+        .iter()
+        .map(|shard| shard.write())
+        .collect::<Vec<_>>();
+    set.mark(count);
+    assert_eq!(MARKED.load(Ordering::Relaxed), 100);
+}
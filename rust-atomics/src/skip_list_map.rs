@@ -0,0 +1,675 @@
+use crossbeam_epoch::{self as epoch, Atomic, Guard, Owned, Shared};
+use std::{
+    cell::Cell,
+    ffi::c_ulong,
+    sync::atomic::{AtomicIsize, AtomicU64, AtomicUsize, Ordering, fence},
+};
+
+const MAX_HEIGHT: usize = 16;
+// tag of a `tower` link whose node is being unlinked from that level
+const MARKED: usize = 1;
+
+// Deleting a key swaps its value with `absent`, which is final for the node:
+// its tower is then marked from the top level down and `find` unlinks marked
+// nodes as it passes them. Unlinked nodes are freed through crossbeam-epoch
+// once no reader can still hold a pointer to them.
+struct Node {
+    key: i64,
+    value: AtomicU64,
+    tower: Box<[Atomic<Node>]>,
+    // the inserter (done linking upper levels) and the deleter (done marking)
+    // both release the node, the last of them retires it
+    owners: AtomicUsize,
+}
+
+static NEXT_SEED: AtomicU64 = AtomicU64::new(0x9E37_79B9_7F4A_7C15);
+
+thread_local! {
+    static RNG_STATE: Cell<u64> =
+        Cell::new(NEXT_SEED.fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed) | 1);
+}
+
+fn random_height() -> usize {
+    RNG_STATE.with(|state| {
+        let mut x = state.get();
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        state.set(x);
+        // every next level is taken with p = 1/2
+        (x.trailing_ones() as usize + 1).min(MAX_HEIGHT)
+    })
+}
+
+pub struct ConcurrentSkipListMap {
+    head: [Atomic<Node>; MAX_HEIGHT],
+    absent: c_ulong,
+    // updated after linking or emptying a node, so when `set` and `delete`
+    // race on a new key it can briefly go below zero
+    len: AtomicIsize,
+}
+
+type Path<'g> = [Shared<'g, Node>; MAX_HEIGHT];
+
+impl ConcurrentSkipListMap {
+    fn alloc() -> Self {
+        Self {
+            head: std::array::from_fn(|_| Atomic::null()),
+            absent: 0,
+            len: AtomicIsize::new(0),
+        }
+    }
+
+    fn init(&mut self, absent: c_ulong) {
+        self.absent = absent;
+    }
+
+    // null stands for the head
+    fn tower<'g>(&'g self, node: Shared<'g, Node>) -> &'g [Atomic<Node>] {
+        match unsafe { node.as_ref() } {
+            Some(node) => &node.tower,
+            None => &self.head,
+        }
+    }
+
+    // On every level returns the last node with a key less than `key` and
+    // its successor, unlinking marked nodes on the way.
+    fn find<'g>(&'g self, key: i64, guard: &'g Guard) -> (Path<'g>, Path<'g>) {
+        'retry: loop {
+            let mut preds = [Shared::null(); MAX_HEIGHT];
+            let mut succs = [Shared::null(); MAX_HEIGHT];
+            let mut pred = Shared::null();
+            for level in (0..MAX_HEIGHT).rev() {
+                let mut curr = self.tower(pred)[level].load(Ordering::Acquire, guard);
+                if curr.tag() == MARKED {
+                    // `pred` itself is being deleted
+                    continue 'retry;
+                }
+                while let Some(node) = unsafe { curr.as_ref() } {
+                    let succ = node.tower[level].load(Ordering::Acquire, guard);
+                    if succ.tag() == MARKED {
+                        match self.tower(pred)[level].compare_exchange(
+                            curr,
+                            succ.with_tag(0),
+                            Ordering::AcqRel,
+                            Ordering::Acquire,
+                            guard,
+                        ) {
+                            Ok(_) => curr = succ.with_tag(0),
+                            Err(_) => continue 'retry,
+                        }
+                        continue;
+                    }
+                    if node.key >= key {
+                        break;
+                    }
+                    pred = curr;
+                    curr = succ;
+                }
+                preds[level] = pred;
+                succs[level] = curr;
+            }
+            return (preds, succs);
+        }
+    }
+
+    // Last node with a key less than or equal to `key`, it may be deleted
+    fn find_last_le<'g>(&'g self, key: i64, guard: &'g Guard) -> Option<&'g Node> {
+        let mut pred = Shared::null();
+        for level in (0..MAX_HEIGHT).rev() {
+            let mut curr = self.tower(pred)[level].load(Ordering::Acquire, guard);
+            while let Some(node) = unsafe { curr.with_tag(0).as_ref() }
+                && node.key <= key
+            {
+                pred = curr.with_tag(0);
+                curr = node.tower[level].load(Ordering::Acquire, guard);
+            }
+        }
+        unsafe { pred.as_ref() }
+    }
+
+    fn live_value(&self, node: &Node) -> Option<c_ulong> {
+        let value = node.value.load(Ordering::Acquire);
+        (value != self.absent).then_some(value)
+    }
+
+    fn live_nodes_from<'g>(
+        &'g self,
+        node: Shared<'g, Node>,
+        guard: &'g Guard,
+    ) -> impl Iterator<Item = (i64, c_ulong)> + 'g {
+        let mut curr = node;
+        std::iter::from_fn(move || {
+            let node = unsafe { curr.with_tag(0).as_ref() }?;
+            curr = node.tower[0].load(Ordering::Acquire, guard);
+            Some(node)
+        })
+        .filter_map(|node| Some((node.key, self.live_value(node)?)))
+    }
+
+    fn get(&self, key: i64) -> Option<c_ulong> {
+        let guard = &epoch::pin();
+        let (_, succs) = self.find(key, guard);
+        let node = unsafe { succs[0].as_ref() }.filter(|node| node.key == key)?;
+        self.live_value(node)
+    }
+
+    fn set(&self, key: i64, value: c_ulong) {
+        let guard = &epoch::pin();
+        let mut new_node = None;
+        loop {
+            let (preds, succs) = self.find(key, guard);
+            if let Some(node) = unsafe { succs[0].as_ref() }
+                && node.key == key
+            {
+                let mut current = node.value.load(Ordering::Acquire);
+                while current != self.absent {
+                    match node.value.compare_exchange_weak(
+                        current,
+                        value,
+                        Ordering::AcqRel,
+                        Ordering::Acquire,
+                    ) {
+                        Ok(_) => return,
+                        Err(actual) => current = actual,
+                    }
+                }
+                // the node is being deleted, help unlinking it and insert a new one
+                Self::mark_tower(node, guard);
+                continue;
+            }
+
+            let node = new_node.take().unwrap_or_else(|| {
+                Owned::new(Node {
+                    key,
+                    value: AtomicU64::new(value),
+                    tower: (0..random_height()).map(|_| Atomic::null()).collect(),
+                    owners: AtomicUsize::new(2),
+                })
+            });
+            node.tower[0].store(succs[0], Ordering::Relaxed);
+            match self.tower(preds[0])[0].compare_exchange(
+                succs[0],
+                node,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+                guard,
+            ) {
+                Ok(node) => {
+                    self.len.fetch_add(1, Ordering::Relaxed);
+                    self.link_upper_levels(node, preds, succs, guard);
+                    return;
+                }
+                Err(err) => new_node = Some(err.new),
+            }
+        }
+    }
+
+    // The node is already reachable on level 0, upper levels are only
+    // shortcuts, so they are linked one by one until the node gets marked.
+    fn link_upper_levels<'g>(
+        &'g self,
+        node: Shared<'g, Node>,
+        mut preds: Path<'g>,
+        mut succs: Path<'g>,
+        guard: &'g Guard,
+    ) {
+        let node_ref = unsafe { node.deref() };
+        'levels: for level in 1..node_ref.tower.len() {
+            loop {
+                // fails once the deleter has marked this level
+                let next = node_ref.tower[level].load(Ordering::Acquire, guard);
+                if next.tag() == MARKED
+                    || node_ref.tower[level]
+                        .compare_exchange(
+                            next,
+                            succs[level],
+                            Ordering::AcqRel,
+                            Ordering::Acquire,
+                            guard,
+                        )
+                        .is_err()
+                {
+                    break 'levels;
+                }
+                if self.tower(preds[level])[level]
+                    .compare_exchange(
+                        succs[level],
+                        node,
+                        Ordering::AcqRel,
+                        Ordering::Acquire,
+                        guard,
+                    )
+                    .is_ok()
+                {
+                    break;
+                }
+                (preds, succs) = self.find(node_ref.key, guard);
+            }
+        }
+        // the deleter may have unlinked the node before the last level was
+        // linked, in which case unlinking it again is up to us
+        fence(Ordering::SeqCst);
+        if node_ref.tower[0].load(Ordering::Relaxed, guard).tag() == MARKED {
+            self.find(node_ref.key, guard);
+        }
+        Self::release(node, guard);
+    }
+
+    fn mark_tower(node: &Node, guard: &Guard) {
+        for next in node.tower.iter().rev() {
+            next.fetch_or(MARKED, Ordering::AcqRel, guard);
+        }
+    }
+
+    // Called by the inserter and the deleter once they no longer link the node
+    fn release(node: Shared<'_, Node>, guard: &Guard) {
+        let owners = unsafe { &node.deref().owners };
+        if owners.fetch_sub(1, Ordering::AcqRel) == 1 {
+            unsafe { guard.defer_destroy(node) };
+        }
+    }
+
+    fn delete(&self, key: i64) -> Option<c_ulong> {
+        let guard = &epoch::pin();
+        let (_, succs) = self.find(key, guard);
+        let node = unsafe { succs[0].as_ref() }.filter(|node| node.key == key)?;
+        let value = node.value.swap(self.absent, Ordering::AcqRel);
+        if value == self.absent {
+            return None;
+        }
+        self.len.fetch_sub(1, Ordering::Relaxed);
+        Self::mark_tower(node, guard);
+        fence(Ordering::SeqCst);
+        self.find(key, guard);
+        Self::release(succs[0], guard);
+        Some(value)
+    }
+
+    fn ceiling(&self, key: i64) -> Option<(i64, c_ulong)> {
+        let guard = &epoch::pin();
+        let (_, succs) = self.find(key, guard);
+        self.live_nodes_from(succs[0], guard).next()
+    }
+
+    fn floor(&self, mut key: i64) -> Option<(i64, c_ulong)> {
+        let guard = &epoch::pin();
+        loop {
+            let node = self.find_last_le(key, guard)?;
+            if let Some(value) = self.live_value(node) {
+                return Some((node.key, value));
+            }
+            key = node.key.checked_sub(1)?;
+        }
+    }
+
+    fn first(&self) -> Option<(i64, c_ulong)> {
+        self.ceiling(i64::MIN)
+    }
+
+    fn last(&self) -> Option<(i64, c_ulong)> {
+        self.floor(i64::MAX)
+    }
+
+    fn range(&self, lo: i64, hi: i64) -> Vec<(i64, c_ulong)> {
+        let guard = &epoch::pin();
+        let (_, succs) = self.find(lo, guard);
+        self.live_nodes_from(succs[0], guard)
+            .take_while(|(key, _)| *key <= hi)
+            .collect()
+    }
+
+    fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed).max(0) as usize
+    }
+
+    fn clear(&self) {
+        let guard = &epoch::pin();
+        let keys = self.live_nodes_from(self.head[0].load(Ordering::Acquire, guard), guard);
+        for (key, _) in keys {
+            self.delete(key);
+        }
+    }
+
+    fn mark(&self, f: extern "C" fn(c_ulong)) {
+        let guard = &epoch::pin();
+        for (_, value) in self.live_nodes_from(self.head[0].load(Ordering::Acquire, guard), guard) {
+            f(value);
+        }
+    }
+}
+
+impl Drop for ConcurrentSkipListMap {
+    fn drop(&mut self) {
+        // deleted nodes are unlinked and already handed over to the collector,
+        // everything still reachable on level 0 is owned by the map
+        let guard = unsafe { epoch::unprotected() };
+        let mut curr = self.head[0].load(Ordering::Relaxed, guard);
+        while !curr.is_null() {
+            let node = unsafe { curr.into_owned() };
+            curr = node.tower[0].load(Ordering::Relaxed, guard).with_tag(0);
+        }
+    }
+}
+
+unsafe fn write_entry(
+    entry: Option<(i64, c_ulong)>,
+    key_out: *mut i64,
+    value_out: *mut c_ulong,
+) -> bool {
+    match entry {
+        Some((key, value)) => {
+            unsafe {
+                key_out.write(key);
+                value_out.write(value);
+            }
+            true
+        }
+        None => false,
+    }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_skip_list_map_alloc(map: *mut ConcurrentSkipListMap) {
+    unsafe { map.write(ConcurrentSkipListMap::alloc()) }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_skip_list_map_init(
+    map: *mut ConcurrentSkipListMap,
+    absent: c_ulong,
+) {
+    let map = unsafe { map.as_mut().unwrap() };
+    map.init(absent);
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_skip_list_map_drop(map: *mut ConcurrentSkipListMap) {
+    unsafe { std::ptr::drop_in_place(map) };
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_skip_list_map_mark(
+    map: *const ConcurrentSkipListMap,
+    f: extern "C" fn(c_ulong),
+) {
+    let map = unsafe { map.as_ref().unwrap() };
+    map.mark(f);
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_skip_list_map_get(
+    map: *const ConcurrentSkipListMap,
+    key: i64,
+    fallback: c_ulong,
+) -> c_ulong {
+    let map = unsafe { map.as_ref().unwrap() };
+    map.get(key).unwrap_or(fallback)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_skip_list_map_set(
+    map: *const ConcurrentSkipListMap,
+    key: i64,
+    value: c_ulong,
+) {
+    let map = unsafe { map.as_ref().unwrap() };
+    map.set(key, value);
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_skip_list_map_delete(
+    map: *const ConcurrentSkipListMap,
+    key: i64,
+    fallback: c_ulong,
+) -> c_ulong {
+    let map = unsafe { map.as_ref().unwrap() };
+    map.delete(key).unwrap_or(fallback)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_skip_list_map_first(
+    map: *const ConcurrentSkipListMap,
+    key: *mut i64,
+    value: *mut c_ulong,
+) -> bool {
+    let map = unsafe { map.as_ref().unwrap() };
+    unsafe { write_entry(map.first(), key, value) }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_skip_list_map_last(
+    map: *const ConcurrentSkipListMap,
+    key: *mut i64,
+    value: *mut c_ulong,
+) -> bool {
+    let map = unsafe { map.as_ref().unwrap() };
+    unsafe { write_entry(map.last(), key, value) }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_skip_list_map_floor(
+    map: *const ConcurrentSkipListMap,
+    target: i64,
+    key: *mut i64,
+    value: *mut c_ulong,
+) -> bool {
+    let map = unsafe { map.as_ref().unwrap() };
+    unsafe { write_entry(map.floor(target), key, value) }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_skip_list_map_ceiling(
+    map: *const ConcurrentSkipListMap,
+    target: i64,
+    key: *mut i64,
+    value: *mut c_ulong,
+) -> bool {
+    let map = unsafe { map.as_ref().unwrap() };
+    unsafe { write_entry(map.ceiling(target), key, value) }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_skip_list_map_range(
+    map: *const ConcurrentSkipListMap,
+    lo: i64,
+    hi: i64,
+    keys: *mut i64,
+    values: *mut c_ulong,
+    cap: usize,
+) -> usize {
+    let map = unsafe { map.as_ref().unwrap() };
+    let entries = map.range(lo, hi);
+    if entries.len() <= cap {
+        for (idx, (key, value)) in entries.iter().enumerate() {
+            unsafe {
+                keys.add(idx).write(*key);
+                values.add(idx).write(*value);
+            }
+        }
+    }
+    entries.len()
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_skip_list_map_size(map: *const ConcurrentSkipListMap) -> usize {
+    let map = unsafe { map.as_ref().unwrap() };
+    map.len()
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_skip_list_map_clear(map: *const ConcurrentSkipListMap) {
+    let map = unsafe { map.as_ref().unwrap() };
+    map.clear();
+}
+
+pub const CONCURRENT_SKIP_LIST_MAP_SIZE: usize = 144;
+
+#[cfg(test)]
+const ABSENT: c_ulong = c_ulong::MAX;
+
+#[cfg(test)]
+impl ConcurrentSkipListMap {
+    // Nodes still reachable on any level, deleted ones included
+    fn linked_nodes(&self) -> usize {
+        let guard = &epoch::pin();
+        (0..MAX_HEIGHT)
+            .map(|level| {
+                let mut count = 0;
+                let mut curr = self.head[level].load(Ordering::Acquire, guard);
+                while let Some(node) = unsafe { curr.with_tag(0).as_ref() } {
+                    count += 1;
+                    curr = node.tower[level].load(Ordering::Acquire, guard);
+                }
+                count
+            })
+            .sum()
+    }
+
+    fn tower_heights(&self) -> usize {
+        let guard = &epoch::pin();
+        let mut heights = 0;
+        let mut curr = self.head[0].load(Ordering::Acquire, guard);
+        while let Some(node) = unsafe { curr.with_tag(0).as_ref() } {
+            heights += node.tower.len();
+            curr = node.tower[0].load(Ordering::Acquire, guard);
+        }
+        heights
+    }
+}
+
+#[test]
+fn test_concurrent_skip_list_map() {
+    assert_eq!(
+        CONCURRENT_SKIP_LIST_MAP_SIZE,
+        std::mem::size_of::<ConcurrentSkipListMap>(),
+        "size mismatch"
+    );
+    assert!(crate::is_sync_and_send::<ConcurrentSkipListMap>());
+
+    let mut map = ConcurrentSkipListMap::alloc();
+    map.init(ABSENT);
+    assert_eq!(map.first(), None);
+    assert_eq!(map.last(), None);
+
+    for key in [50, 10, 30, 40, 20] {
+        map.set(key, key as c_ulong * 10);
+    }
+    map.set(30, 333);
+    assert_eq!(map.get(30), Some(333));
+    assert_eq!(map.get(35), None);
+    assert_eq!(map.first(), Some((10, 100)));
+    assert_eq!(map.last(), Some((50, 500)));
+    assert_eq!(map.range(15, 40), [(20, 200), (30, 333), (40, 400)]);
+    assert_eq!(map.floor(35), Some((30, 333)));
+    assert_eq!(map.ceiling(35), Some((40, 400)));
+    assert_eq!(map.floor(5), None);
+    assert_eq!(map.ceiling(55), None);
+
+    assert_eq!(map.delete(50), Some(500));
+    assert_eq!(map.delete(50), None);
+    assert_eq!(map.delete(30), Some(333));
+    assert_eq!(map.last(), Some((40, 400)));
+    assert_eq!(map.floor(39), Some((20, 200)));
+    assert_eq!(map.len(), 3);
+
+    map.set(50, 5);
+    assert_eq!(map.last(), Some((50, 5)));
+    assert_eq!(map.len(), 4);
+    assert_eq!(map.linked_nodes(), map.tower_heights());
+    assert_eq!(
+        map.range(i64::MIN, i64::MAX),
+        [(10, 100), (20, 200), (40, 400), (50, 5)]
+    );
+    map.clear();
+    assert_eq!(map.len(), 0);
+    assert_eq!(map.first(), None);
+    assert!(
+        map.head
+            .iter()
+            .all(|next| next.load(Ordering::Relaxed, &epoch::pin()).is_null())
+    );
+}
+
+#[test]
+fn test_concurrent_skip_list_map_sliding_window() {
+    let mut map = ConcurrentSkipListMap::alloc();
+    map.init(ABSENT);
+    // time-indexed keys: new ones keep arriving, old ones are deleted
+    for key in 0..100_000 {
+        map.set(key, key as c_ulong);
+        if key >= 10 {
+            map.delete(key - 10);
+        }
+    }
+    assert_eq!(map.len(), 10);
+    // deleted nodes are unlinked from every level right away
+    assert_eq!(map.linked_nodes(), map.tower_heights());
+    assert_eq!(map.first(), Some((99_990, 99_990)));
+}
+
+#[test]
+fn test_concurrent_skip_list_map_threads() {
+    let mut map = ConcurrentSkipListMap::alloc();
+    map.init(ABSENT);
+    let map = std::sync::Arc::new(map);
+    let threads = (0..4)
+        .map(|thread_idx| {
+            let map = std::sync::Arc::clone(&map);
+            std::thread::spawn(move || {
+                for key in (thread_idx..4_000).step_by(4) {
+                    map.set(key, key as c_ulong);
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    let expected = (0..4_000)
+        .map(|key| (key, key as c_ulong))
+        .collect::<Vec<_>>();
+    assert_eq!(map.range(i64::MIN, i64::MAX), expected);
+
+    // deletes unlink nodes while other threads keep reading and writing
+    let threads = (0..4)
+        .map(|thread_idx| {
+            let map = std::sync::Arc::clone(&map);
+            std::thread::spawn(move || {
+                for key in (thread_idx..4_000).step_by(4) {
+                    assert_eq!(map.get(key), Some(key as c_ulong));
+                    if key >= 1_000 {
+                        assert_eq!(map.delete(key), Some(key as c_ulong));
+                    }
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert_eq!(map.len(), 1_000);
+    assert_eq!(map.range(i64::MIN, i64::MAX), expected[..1_000]);
+    assert_eq!(map.linked_nodes(), map.tower_heights());
+
+    // every thread sets and deletes the same keys
+    let threads = (0..4)
+        .map(|_| {
+            let map = std::sync::Arc::clone(&map);
+            std::thread::spawn(move || {
+                for round in 0..2_000 {
+                    let key = 1_000 + round % 16;
+                    map.set(key, round as c_ulong);
+                    map.delete(key);
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert_eq!(map.len(), 1_000);
+    assert_eq!(map.range(i64::MIN, i64::MAX), expected[..1_000]);
+    assert_eq!(map.linked_nodes(), map.tower_heights());
+}
@@ -0,0 +1,137 @@
+use std::{
+    cell::Cell,
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+};
+
+#[repr(align(64))]
+#[derive(Debug)]
+struct PaddedCell {
+    value: AtomicU64,
+}
+
+#[derive(Debug)]
+pub struct StripedCounter {
+    cells: Box<[PaddedCell]>,
+}
+
+static NEXT_THREAD_IDX: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static THREAD_IDX: Cell<usize> = Cell::new(NEXT_THREAD_IDX.fetch_add(1, Ordering::Relaxed));
+}
+
+impl StripedCounter {
+    fn alloc() -> Self {
+        Self {
+            cells: Box::new([]),
+        }
+    }
+
+    fn init(&mut self, stripes: usize) {
+        assert!(stripes >= 1);
+        self.cells = (0..stripes)
+            .map(|_| PaddedCell {
+                value: AtomicU64::new(0),
+            })
+            .collect();
+    }
+
+    pub fn new(stripes: usize) -> Self {
+        let mut counter = Self::alloc();
+        counter.init(stripes);
+        counter
+    }
+
+    fn cell(&self) -> &PaddedCell {
+        let idx = THREAD_IDX.with(|idx| idx.get());
+        &self.cells[idx % self.cells.len()]
+    }
+
+    pub fn add(&self, delta: u64) {
+        self.cell().value.fetch_add(delta, Ordering::Relaxed);
+    }
+
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    pub fn read(&self) -> u64 {
+        self.cells
+            .iter()
+            .map(|cell| cell.value.load(Ordering::Relaxed))
+            .fold(0, u64::wrapping_add)
+    }
+
+    pub fn get_and_reset(&self) -> u64 {
+        self.cells
+            .iter()
+            .map(|cell| cell.value.swap(0, Ordering::Relaxed))
+            .fold(0, u64::wrapping_add)
+    }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn striped_counter_alloc(counter: *mut StripedCounter) {
+    unsafe { counter.write(StripedCounter::alloc()) }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn striped_counter_init(counter: *mut StripedCounter, stripes: usize) {
+    let counter = unsafe { counter.as_mut().unwrap() };
+    counter.init(stripes);
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn striped_counter_drop(counter: *mut StripedCounter) {
+    unsafe { std::ptr::drop_in_place(counter) };
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn striped_counter_increment(counter: *const StripedCounter) {
+    let counter = unsafe { counter.as_ref().unwrap() };
+    counter.inc();
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn striped_counter_add(counter: *const StripedCounter, delta: u64) {
+    let counter = unsafe { counter.as_ref().unwrap() };
+    counter.add(delta);
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn striped_counter_read(counter: *const StripedCounter) -> u64 {
+    let counter = unsafe { counter.as_ref().unwrap() };
+    counter.read()
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn striped_counter_get_and_reset(counter: *const StripedCounter) -> u64 {
+    let counter = unsafe { counter.as_ref().unwrap() };
+    counter.get_and_reset()
+}
+
+pub const STRIPED_COUNTER_SIZE: usize = 16;
+
+#[test]
+fn test_striped_counter() {
+    assert_eq!(
+        STRIPED_COUNTER_SIZE,
+        std::mem::size_of::<StripedCounter>(),
+        "size mismatch"
+    );
+    assert!(crate::is_sync_and_send::<StripedCounter>());
+
+    let counter = std::sync::Arc::new(StripedCounter::new(4));
+    let threads = (0..8)
+        .map(|_| {
+            let counter = std::sync::Arc::clone(&counter);
+            std::thread::spawn(move || (0..1_000).for_each(|_| counter.inc()))
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert_eq!(counter.read(), 8_000);
+    assert_eq!(counter.get_and_reset(), 8_000);
+    assert_eq!(counter.read(), 0);
+}
@@ -0,0 +1,249 @@
+use crate::hashmap::{HashedKey, mark_shards};
+use std::{
+    ffi::c_ulong,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+pub const CONCURRENT_TTL_MAP_NO_TTL: u64 = u64::MAX;
+
+// every N-th `set` sweeps the whole map, so keys that are never read again
+// are still dropped eventually
+const PURGE_EVERY_N_WRITES: usize = 1024;
+
+#[derive(Debug, Clone, Copy)]
+struct TtlEntry {
+    value: c_ulong,
+    expires_at: Option<Instant>,
+}
+
+impl TtlEntry {
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+pub struct ConcurrentTtlMap {
+    map: dashmap::DashMap<HashedKey, TtlEntry>,
+    writes: AtomicUsize,
+}
+
+impl ConcurrentTtlMap {
+    fn new() -> Self {
+        Self {
+            map: dashmap::DashMap::new(),
+            writes: AtomicUsize::new(0),
+        }
+    }
+
+    fn get(&self, key: c_ulong) -> Option<c_ulong> {
+        let key = HashedKey::new(key);
+        let now = Instant::now();
+        let entry = *self.map.get(&key)?;
+        if entry.is_expired(now) {
+            self.map.remove_if(&key, |_, entry| entry.is_expired(now));
+            return None;
+        }
+        Some(entry.value)
+    }
+
+    fn set(&self, key: c_ulong, value: c_ulong, ttl: Option<Duration>) {
+        let now = Instant::now();
+        let entry = TtlEntry {
+            value,
+            // TTLs beyond what `Instant` can represent never expire
+            expires_at: ttl.and_then(|ttl| now.checked_add(ttl)),
+        };
+        self.map.insert(HashedKey::new(key), entry);
+
+        let writes = self.writes.fetch_add(1, Ordering::Relaxed);
+        if writes.is_multiple_of(PURGE_EVERY_N_WRITES) {
+            self.purge_expired();
+        }
+    }
+
+    fn delete(&self, key: c_ulong) -> Option<c_ulong> {
+        let now = Instant::now();
+        self.map
+            .remove(&HashedKey::new(key))
+            .map(|(_, entry)| entry)
+            .filter(|entry| !entry.is_expired(now))
+            .map(|entry| entry.value)
+    }
+
+    fn contains_key(&self, key: c_ulong) -> bool {
+        self.get(key).is_some()
+    }
+
+    fn len(&self) -> usize {
+        let now = Instant::now();
+        self.map
+            .iter()
+            .filter(|pair| !pair.value().is_expired(now))
+            .count()
+    }
+
+    fn clear(&self) {
+        self.map.clear()
+    }
+
+    fn purge_expired(&self) -> usize {
+        let now = Instant::now();
+        let mut purged = 0;
+        self.map.retain(|_, entry| {
+            let is_expired = entry.is_expired(now);
+            purged += usize::from(is_expired);
+            !is_expired
+        });
+        purged
+    }
+
+    // Expired entries are still marked: their keys are hashed and compared
+    // on lookups until they are purged.
+    fn mark(&self, f: extern "C" fn(c_ulong)) {
+        mark_shards(&self.map, |key, entry| {
+            f(key.key.0);
+            f(entry.value);
+        });
+    }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_ttl_map_init(ttl_map: *mut ConcurrentTtlMap) {
+    unsafe { ttl_map.write(ConcurrentTtlMap::new()) }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_ttl_map_drop(ttl_map: *mut ConcurrentTtlMap) {
+    unsafe { std::ptr::drop_in_place(ttl_map) };
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_ttl_map_mark(
+    ttl_map: *const ConcurrentTtlMap,
+    f: extern "C" fn(c_ulong),
+) {
+    let ttl_map = unsafe { ttl_map.as_ref().unwrap() };
+    ttl_map.mark(f);
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_ttl_map_get(
+    ttl_map: *const ConcurrentTtlMap,
+    key: c_ulong,
+    fallback: c_ulong,
+) -> c_ulong {
+    let ttl_map = unsafe { ttl_map.as_ref().unwrap() };
+    ttl_map.get(key).unwrap_or(fallback)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_ttl_map_set(
+    ttl_map: *const ConcurrentTtlMap,
+    key: c_ulong,
+    value: c_ulong,
+    ttl_ms: u64,
+) {
+    let ttl_map = unsafe { ttl_map.as_ref().unwrap() };
+    let ttl = (ttl_ms != CONCURRENT_TTL_MAP_NO_TTL).then(|| Duration::from_millis(ttl_ms));
+    ttl_map.set(key, value, ttl);
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_ttl_map_delete(
+    ttl_map: *const ConcurrentTtlMap,
+    key: c_ulong,
+    fallback: c_ulong,
+) -> c_ulong {
+    let ttl_map = unsafe { ttl_map.as_ref().unwrap() };
+    ttl_map.delete(key).unwrap_or(fallback)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_ttl_map_contains_key(
+    ttl_map: *const ConcurrentTtlMap,
+    key: c_ulong,
+) -> bool {
+    let ttl_map = unsafe { ttl_map.as_ref().unwrap() };
+    ttl_map.contains_key(key)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_ttl_map_size(ttl_map: *const ConcurrentTtlMap) -> usize {
+    let ttl_map = unsafe { ttl_map.as_ref().unwrap() };
+    ttl_map.len()
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_ttl_map_clear(ttl_map: *const ConcurrentTtlMap) {
+    let ttl_map = unsafe { ttl_map.as_ref().unwrap() };
+    ttl_map.clear();
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn concurrent_ttl_map_purge_expired(
+    ttl_map: *const ConcurrentTtlMap,
+) -> usize {
+    let ttl_map = unsafe { ttl_map.as_ref().unwrap() };
+    ttl_map.purge_expired()
+}
+
+pub const CONCURRENT_TTL_MAP_SIZE: usize = 48;
+
+#[test]
+fn test_concurrent_ttl_map() {
+    assert_eq!(
+        CONCURRENT_TTL_MAP_SIZE,
+        std::mem::size_of::<ConcurrentTtlMap>(),
+        "size mismatch"
+    );
+    assert!(crate::is_sync_and_send::<ConcurrentTtlMap>());
+}
+
+#[test]
+fn test_concurrent_ttl_map_expiry() {
+    use crate::test_helpers::fix;
+
+    let map = ConcurrentTtlMap::new();
+    map.set(fix(1), fix(10), None);
+    map.set(fix(2), fix(20), Some(Duration::ZERO));
+    map.set(fix(3), fix(30), Some(Duration::from_secs(3600)));
+    map.set(fix(4), fix(40), Some(Duration::ZERO));
+
+    assert_eq!(map.get(fix(1)), Some(fix(10)));
+    assert_eq!(map.get(fix(3)), Some(fix(30)));
+    assert_eq!(map.len(), 2);
+
+    // lazily dropped on access
+    assert_eq!(map.get(fix(2)), None);
+    assert_eq!(map.map.len(), 3);
+
+    assert_eq!(map.purge_expired(), 1);
+    assert_eq!(map.map.len(), 2);
+    assert!(!map.contains_key(fix(4)));
+
+    map.set(fix(5), fix(50), Some(Duration::from_millis(u64::MAX - 1)));
+    assert_eq!(map.get(fix(5)), Some(fix(50)));
+}
+
+#[test]
+fn test_concurrent_ttl_map_mark_under_write_lock() {
+    use crate::test_helpers::fix;
+    static MARKED: AtomicUsize = AtomicUsize::new(0);
+    extern "C" fn count(_: c_ulong) {
+        MARKED.fetch_add(1, Ordering::Relaxed);
+    }
+
+    let map = ConcurrentTtlMap::new();
+    for n in 0..100 {
+        map.set(fix(n), fix(n), None);
+    }
+    let _guards = map
+        .map
+        .shards()
+        .iter()
+        .map(|shard| shard.write())
+        .collect::<Vec<_>>();
+    map.mark(count);
+    assert_eq!(MARKED.load(Ordering::Relaxed), 200);
+}
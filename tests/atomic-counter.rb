@@ -3,13 +3,25 @@ require_relative './helper'
 ITER_COUNT = 1_000_000
 puts "Iterations: #{ITER_COUNT}"
 
+U64_MAX = 2 ** 64 - 1
+
+# Values past the Fixnum range are read back as Bignums
+def assert_saturated_read
+  counter = CAtomics::AtomicCounter.new(:saturating)
+  counter.add(U64_MAX)
+  counter.increment
+  assert_eq(counter.read, U64_MAX, 'saturated counter')
+end
+
 def do_seq
+  assert_saturated_read
   counter = CAtomics::AtomicCounter.new
   (CPU_COUNT * ITER_COUNT).times { counter.increment }
   assert_eq(counter.read, (CPU_COUNT * ITER_COUNT), 'buggy counter')
 end
 
 def do_ractors
+  assert_saturated_read
   counter = CAtomics::AtomicCounter.new
   ractors = 1.upto(CPU_COUNT).map do |i|
     Ractor.new(counter) do |counter|
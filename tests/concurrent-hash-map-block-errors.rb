@@ -0,0 +1,55 @@
+require_relative './helper'
+
+ITER_COUNT = 1_000
+puts "Iterations: #{ITER_COUNT}"
+
+# Blocks run under a shard lock, leaving them with raise, break or throw
+# must release it, otherwise the next access to the shard deadlocks
+def run(map)
+  ITER_COUNT.times do
+    begin
+      map.fetch_and_modify(:counter) { raise 'fetch_and_modify' }
+    rescue RuntimeError
+    end
+    map.fetch_and_modify(:counter) { break }
+    begin
+      map.compute_if_absent(:missing) { raise 'compute_if_absent' }
+    rescue RuntimeError
+    end
+    catch(:skip) { map.compute_if_absent(:missing) { throw :skip } }
+    begin
+      map.upsert(:counter) { raise 'upsert' }
+    rescue RuntimeError
+    end
+    map.upsert(:missing) { break }
+
+    map.fetch_and_modify(:counter) { |value| value + 1 }
+  end
+end
+
+def assert_map(map)
+  assert_eq(map.get(:counter), CPU_COUNT * ITER_COUNT, 'lost updates')
+  assert_eq(map.key?(:missing), false, 'value of a raising block is stored')
+end
+
+def do_seq
+  map = CAtomics::ConcurrentHashMap.new
+  map.set(:counter, 0)
+  CPU_COUNT.times { run(map) }
+  assert_map(map)
+end
+
+def do_ractors
+  map = CAtomics::ConcurrentHashMap.new
+  map.set(:counter, 0)
+  ractors = 1.upto(CPU_COUNT).map do
+    Ractor.new(map) do |map|
+      run(map)
+      Ractor.yield :done
+    end
+  end
+  assert_eq(ractors.map(&:take), [:done] * CPU_COUNT, 'not all ractors have finished successfully')
+  assert_map(map)
+end
+
+process_args
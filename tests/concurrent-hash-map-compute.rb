@@ -0,0 +1,36 @@
+require_relative './helper'
+
+ITER_COUNT = 100_000
+puts "Iterations: #{ITER_COUNT}"
+
+# The block reads the map it is computing, which deadlocks in `upsert`
+def run(map)
+  ITER_COUNT.times do
+    map.compute(:counter) do |value|
+      step = map.get(:step)
+      value.equal?(CAtomics::UNDEFINED) ? step : value + step
+    end
+  end
+end
+
+def do_seq
+  map = CAtomics::ConcurrentHashMap.new
+  map.set(:step, 1)
+  CPU_COUNT.times { run(map) }
+  assert_eq(map.get(:counter), CPU_COUNT * ITER_COUNT, 'lost updates')
+end
+
+def do_ractors
+  map = CAtomics::ConcurrentHashMap.new
+  map.set(:step, 1)
+  ractors = 1.upto(CPU_COUNT).map do
+    Ractor.new(map) do |map|
+      run(map)
+      Ractor.yield :done
+    end
+  end
+  assert_eq(ractors.map(&:take), [:done] * CPU_COUNT, 'not all ractors have finished successfully')
+  assert_eq(map.get(:counter), CPU_COUNT * ITER_COUNT, 'lost updates')
+end
+
+process_args
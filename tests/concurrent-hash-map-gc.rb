@@ -0,0 +1,36 @@
+require_relative './helper'
+
+ITER_COUNT = 100
+puts "Iterations: #{ITER_COUNT}"
+
+# GC started from a callback that holds a shard lock must still be able to mark the map
+def run(map)
+  ITER_COUNT.times do
+    map.fetch_and_modify(:counter) do |value|
+      GC.start
+      value + 1
+    end
+  end
+end
+
+def do_seq
+  map = CAtomics::ConcurrentHashMap.new
+  map.set(:counter, 0)
+  CPU_COUNT.times { run(map) }
+  assert_eq(map.get(:counter), CPU_COUNT * ITER_COUNT, 'lost updates')
+end
+
+# Only one Ractor mutates the map: another one blocked on the same shard lock
+# would never reach the VM barrier that GC waits for
+def do_ractors
+  map = CAtomics::ConcurrentHashMap.new
+  map.set(:counter, 0)
+  ractor = Ractor.new(map) do |map|
+    CPU_COUNT.times { run(map) }
+    Ractor.yield :done
+  end
+  assert_eq(ractor.take, :done, 'ractor has not finished successfully')
+  assert_eq(map.get(:counter), CPU_COUNT * ITER_COUNT, 'lost updates')
+end
+
+process_args
@@ -0,0 +1,98 @@
+require_relative './helper'
+
+ITER_COUNT = 1_000
+puts "Iterations: #{ITER_COUNT}"
+
+EVENTS_COUNT = CPU_COUNT * ITER_COUNT
+QUEUE_CAPACITY = 2 ** Math.log2(EVENTS_COUNT).ceil
+
+def assert_events(queue)
+  events = EVENTS_COUNT.times.map { queue.pop }
+  assert_eq(events.map(&:first).sum, CPU_COUNT * (0...ITER_COUNT).sum, 'lost events')
+  assert_eq(events.count { |_key, old, _new| old.nil? }, ITER_COUNT, 'wrong old values')
+end
+
+# Returns events published while the block runs
+def published(queue)
+  yield
+  queue.push(:done)
+  events = []
+  while (event = queue.pop) != :done
+    events << event
+  end
+  events
+end
+
+def assert_writer_events
+  map = CAtomics::ConcurrentHashMap.new
+  queue = map.subscribe
+
+  events = published(queue) { map.set_many([1, 2], [10, 20]) }
+  assert_eq(events, [[1, nil, 10], [2, nil, 20]], 'set_many')
+
+  events = published(queue) { map.delete_many([1, 3]) }
+  assert_eq(events, [[1, 10, nil]], 'delete_many')
+
+  other = CAtomics::ConcurrentHashMap.new
+  other.set_many([2, 3], [200, 300])
+  events = published(queue) { map.merge(other) }.sort
+  assert_eq(events, [[2, 20, 200], [3, nil, 300]], 'merge')
+
+  events = published(queue) { map.merge(map) }.sort
+  assert_eq(events, [[2, 200, 200], [3, 300, 300]], 'merge into itself')
+
+  events = published(queue) { 2.times { |n| map.put_if_absent(4, 40 + n) } }
+  assert_eq(events, [[4, nil, 40]], 'put_if_absent')
+
+  events = published(queue) { 2.times { |n| map.compute_if_absent(5) { 50 + n } } }
+  assert_eq(events, [[5, nil, 50]], 'compute_if_absent')
+
+  events = published(queue) do
+    map.fetch_and_modify(5) { |v| v + 1 }
+    map.fetch_and_modify(6) { 60 }
+  end
+  assert_eq(events, [[5, 50, 51]], 'fetch_and_modify')
+
+  events = published(queue) do
+    2.times { map.upsert(6) { |v| v.equal?(CAtomics::UNDEFINED) ? 60 : CAtomics::UNDEFINED } }
+    map.upsert(7) { CAtomics::UNDEFINED }
+  end
+  assert_eq(events, [[6, nil, 60], [6, 60, nil]], 'upsert')
+
+  events = published(queue) do
+    2.times { map.compute(7) { |v| v.equal?(CAtomics::UNDEFINED) ? 70 : CAtomics::UNDEFINED } }
+    map.compute(8) { CAtomics::UNDEFINED }
+  end
+  assert_eq(events, [[7, nil, 70], [7, 70, nil]], 'compute')
+
+  events = published(queue) { 2.times { map.clear } }.sort
+  assert_eq(events, [[2, 200, nil], [3, 300, nil], [4, 40, nil], [5, 51, nil]], 'clear')
+end
+
+def do_seq
+  assert_writer_events
+  map = CAtomics::ConcurrentHashMap.new
+  queue = map.subscribe(QUEUE_CAPACITY)
+  CPU_COUNT.times { ITER_COUNT.times { |key| map.set(key, key) } }
+  assert_events(queue)
+end
+
+def do_ractors
+  assert_writer_events
+  map = CAtomics::ConcurrentHashMap.new
+  queue = map.subscribe(QUEUE_CAPACITY)
+  subscriber = Ractor.new(queue) do |queue|
+    assert_events(queue)
+    Ractor.yield :done
+  end
+  writers = 1.upto(CPU_COUNT).map do
+    Ractor.new(map) do |map|
+      ITER_COUNT.times { |key| map.set(key, key) }
+      Ractor.yield :done
+    end
+  end
+  assert_eq(writers.map(&:take), [:done] * CPU_COUNT, 'not all writers have finished successfully')
+  assert_eq(subscriber.take, :done, 'subscriber has not finished successfully')
+end
+
+process_args
@@ -0,0 +1,34 @@
+require_relative './helper'
+
+ITER_COUNT = 10_000
+puts "Iterations: #{ITER_COUNT}"
+
+# Both keys are always written by one transaction,
+# so no reader can ever see them out of sync
+def run(map, id)
+  ITER_COUNT.times do |i|
+    value = id * ITER_COUNT + i
+    map.transaction([[:set, :lhs, value], [:set, :rhs, value]])
+    lhs, rhs = map.transaction([[:get, :lhs], [:get, :rhs]])
+    assert_eq(lhs, rhs, 'torn transaction')
+  end
+end
+
+def do_seq
+  map = CAtomics::ConcurrentHashMap.new
+  assert_eq(map.transaction([[:get, :lhs], [:set, :lhs, 1], [:delete, :lhs]]), [nil, nil, 1], 'wrong pre-images')
+  CPU_COUNT.times { |id| run(map, id) }
+end
+
+def do_ractors
+  map = CAtomics::ConcurrentHashMap.new
+  ractors = 1.upto(CPU_COUNT).map do |id|
+    Ractor.new(map, id) do |map, id|
+      run(map, id)
+      Ractor.yield :done
+    end
+  end
+  assert_eq(ractors.map(&:take), [:done] * CPU_COUNT, 'not all ractors have finished successfully')
+end
+
+process_args
@@ -0,0 +1,43 @@
+require_relative './helper'
+
+ITER_COUNT = 100_000
+puts "Iterations: #{ITER_COUNT}"
+
+CAPACITY = 100
+
+def assert_invalid_arguments
+  assert_raises(ArgumentError, 'zero capacity') { CAtomics::ConcurrentLruCache.new(0) }
+  assert_raises(ArgumentError, 'negative capacity') { CAtomics::ConcurrentLruCache.new(-1) }
+  assert_raises(ArgumentError, 'zero shards') { CAtomics::ConcurrentLruCache.new(CAPACITY, 0) }
+  assert_raises(ArgumentError, 'negative shards') { CAtomics::ConcurrentLruCache.new(CAPACITY, -4) }
+end
+
+def run(cache)
+  ITER_COUNT.times do |n|
+    cache.set(n % (CAPACITY * 2), n)
+    cache.get(n % CAPACITY)
+    GC.start if n % 10_000 == 0
+  end
+end
+
+def do_seq
+  assert_invalid_arguments
+  cache = CAtomics::ConcurrentLruCache.new(CAPACITY)
+  CPU_COUNT.times { run(cache) }
+  assert_eq(cache.size, CAPACITY, 'wrong size')
+end
+
+def do_ractors
+  assert_invalid_arguments
+  cache = CAtomics::ConcurrentLruCache.new(CAPACITY)
+  ractors = 1.upto(CPU_COUNT).map do
+    Ractor.new(cache) do |cache|
+      run(cache)
+      Ractor.yield :done
+    end
+  end
+  assert_eq(ractors.map(&:take), [:done] * CPU_COUNT, 'not all ractors have finished successfully')
+  assert_eq(cache.size, CAPACITY, 'wrong size')
+end
+
+process_args
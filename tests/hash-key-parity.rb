@@ -0,0 +1,43 @@
+require_relative './helper'
+
+# Keys that are compared and hashed natively must behave exactly like Hash keys
+def run
+  pairs = [
+    [0.0, -0.0],
+    [1.5, 1.5],
+    [Float('1e-320'), Float('1e-320')],
+    [1, 1.0],
+    [:abc, 'abc'],
+    ['abc', 'abc'.dup],
+    ['abc'.freeze, 'abc'.b.freeze],
+    ['abc'.freeze, 'abc'.encode('UTF-16LE').freeze],
+    ["é".freeze, "é".b.freeze],
+  ]
+  pairs.each do |lhs, rhs|
+    expected = { lhs => :found }[rhs]
+
+    map = CAtomics::ConcurrentHashMap.new
+    map.set(lhs, :found)
+    assert_eq(map.get(rhs), expected, "ConcurrentHashMap lookup of #{rhs.inspect} by #{lhs.inspect}")
+
+    set = CAtomics::ConcurrentSet.new
+    set.add(lhs)
+    assert_eq(set.include?(rhs), !expected.nil?, "ConcurrentSet lookup of #{rhs.inspect} by #{lhs.inspect}")
+  end
+end
+
+def do_seq
+  run
+end
+
+def do_ractors
+  ractors = 1.upto(CPU_COUNT).map do
+    Ractor.new do
+      run
+      Ractor.yield :done
+    end
+  end
+  assert_eq(ractors.map(&:take), [:done] * CPU_COUNT, 'not all ractors have finished successfully')
+end
+
+process_args
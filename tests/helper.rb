@@ -27,6 +27,13 @@ def assert_ne(lhs, rhs, message)
   raise "#{message}: #{lhs} == #{rhs}" if lhs == rhs
 end
 
+def assert_raises(error_class, message)
+  yield
+rescue error_class
+else
+  raise "#{message}: #{error_class} is not raised"
+end
+
 def do_benchmark
   Benchmark.bmbm do |x|
     x.report("#{CPU_COUNT}x seq") { do_seq }
@@ -0,0 +1,32 @@
+require_relative './helper'
+
+ITER_COUNT = 100_000
+puts "Iterations: #{ITER_COUNT}"
+
+def assert_percentiles(histogram)
+  assert_eq(histogram.count, CPU_COUNT * ITER_COUNT, 'lost records')
+  assert_eq(histogram.min, 1, 'wrong min')
+  assert_eq(histogram.max, ITER_COUNT, 'wrong max')
+  p50 = histogram.percentile(50)
+  raise "p50 is too far from #{ITER_COUNT / 2}: #{p50}" if (p50 - ITER_COUNT / 2).abs > ITER_COUNT / 32
+end
+
+def do_seq
+  histogram = CAtomics::Histogram.new
+  CPU_COUNT.times { 1.upto(ITER_COUNT) { |value| histogram.record(value) } }
+  assert_percentiles(histogram)
+end
+
+def do_ractors
+  histogram = CAtomics::Histogram.new
+  ractors = 1.upto(CPU_COUNT).map do |i|
+    Ractor.new(histogram) do |histogram|
+      1.upto(ITER_COUNT) { |value| histogram.record(value) }
+      Ractor.yield :done
+    end
+  end
+  assert_eq(ractors.map(&:take), [:done] * CPU_COUNT, 'not all workers have finished successfully')
+  assert_percentiles(histogram)
+end
+
+process_args
@@ -0,0 +1,30 @@
+require_relative './helper'
+
+unless CAtomics::PlainCounter.method_defined?(:races)
+  abort 'c_atomics is built without the race detector, run `just plain-counter-race-detector`'
+end
+
+ITER_COUNT = 100_000
+puts "Iterations: #{ITER_COUNT}"
+
+def do_seq
+  counter = CAtomics::PlainCounter.new
+  (CPU_COUNT * ITER_COUNT).times { counter.increment }
+  assert_eq(counter.races, 0, 'race detected in sequential mode')
+end
+
+def do_ractors
+  counter = CAtomics::PlainCounter.new
+  ractors = 1.upto(CPU_COUNT).map do |i|
+    Ractor.new(counter) do |counter|
+      ITER_COUNT.times { counter.increment }
+      Ractor.yield :done
+    end
+  end
+  assert_eq(ractors.map(&:take), [:done] * CPU_COUNT, 'not all workers have finished successfully')
+  assert_ne(counter.races, 0, 'no races detected')
+  assert_ne(counter.last_writer, 0, 'no last writer recorded')
+  puts "Detected races: #{counter.races}, last writer: thread #{counter.last_writer}"
+end
+
+process_args
@@ -0,0 +1,32 @@
+require_relative './helper'
+
+ITER_COUNT = 1_000_000
+puts "Iterations: #{ITER_COUNT}"
+
+def assert_invalid_arguments
+  assert_raises(ArgumentError, 'zero stripes') { CAtomics::StripedCounter.new(0) }
+  assert_raises(ArgumentError, 'negative stripes') { CAtomics::StripedCounter.new(-1) }
+  assert_raises(TypeError, 'non-integer stripes') { CAtomics::StripedCounter.new('4') }
+end
+
+def do_seq
+  assert_invalid_arguments
+  counter = CAtomics::StripedCounter.new(CPU_COUNT)
+  (CPU_COUNT * ITER_COUNT).times { counter.increment }
+  assert_eq(counter.read, (CPU_COUNT * ITER_COUNT), 'buggy counter')
+end
+
+def do_ractors
+  assert_invalid_arguments
+  counter = CAtomics::StripedCounter.new(CPU_COUNT)
+  ractors = 1.upto(CPU_COUNT).map do |i|
+    Ractor.new(counter) do |counter|
+      ITER_COUNT.times { counter.increment }
+      Ractor.yield :done
+    end
+  end
+  assert_eq(ractors.map(&:take), [:done] * CPU_COUNT, 'not all workers have finished successfully')
+  assert_eq(counter.read, CPU_COUNT * ITER_COUNT, 'race condition')
+end
+
+process_args